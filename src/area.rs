@@ -1,20 +1,108 @@
-pub use crate::snake::brick::{Brick, Position};
+use crate::lattice::{Cubic, Lattice, Square};
+pub use crate::snake::brick::{Coord, Position};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 use std::vec::Vec;
 
 pub type Condition = fn(Position) -> bool;
 
+#[derive(Clone)]
 pub struct Area {
     pub conditions: Vec<Condition>,
+    // lower and upper corner (inclusive) of a box around the area
+    pub bounds: Option<(Position, Position)>,
+    // if given, no other cells are in the area
+    pub cells: Option<HashSet<Position>>,
+    // blocked cells, e.g. by an obstacle or bricks already in place
+    pub excluded: HashSet<Position>,
+    // target colour of a cell, any brick may go to a cell without one
+    pub colours: HashMap<Position, char>,
+    pub lattice: Rc<dyn Lattice>,
 }
 
 impl Area {
     pub fn new() -> Area {
         Area {
             conditions: Vec::new(),
+            bounds: None,
+            cells: None,
+            excluded: HashSet::new(),
+            colours: HashMap::new(),
+            lattice: Rc::new(Cubic),
         }
     }
 
+    // empty if a side is 0
+    pub fn cuboid(x: Coord, y: Coord, z: Coord) -> Area {
+        let mut area = Area::new();
+        area.bounds = Some((Position::new(0, 0, 0), Position::new(x - 1, y - 1, z - 1)));
+        area
+    }
+
+    // box between two corners (inclusive)
+    pub fn span(aa: Position, bb: Position) -> Area {
+        let mut area = Area::new();
+        area.bounds = Some((
+            Position::new(aa.x.min(bb.x), aa.y.min(bb.y), aa.z.min(bb.z)),
+            Position::new(aa.x.max(bb.x), aa.y.max(bb.y), aa.z.max(bb.z)),
+        ));
+        area
+    }
+
+    // planar area of width w (along y) and height h (along x)
+    pub fn rect(w: Coord, h: Coord) -> Area {
+        let mut area = Area::cuboid(h, w, 1);
+        area.lattice = Rc::new(Square);
+        area
+    }
+
+    /*
+     * planar area drawn row by row from top to bottom, '#' marks a cell
+     * of the area; the top row has the largest x, as in the renderings
+     */
+    pub fn bitmap(rows: &[&str]) -> Area {
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut area = Area::rect(width as Coord, rows.len() as Coord);
+        let mut cells = HashSet::new();
+        for (ii, row) in rows.iter().enumerate() {
+            for (jj, cc) in row.chars().enumerate() {
+                if cc == '#' {
+                    cells.insert(Position::new(
+                        (rows.len() - 1 - ii) as Coord,
+                        jj as Coord,
+                        0,
+                    ));
+                }
+            }
+        }
+        area.cells = Some(cells);
+        area
+    }
+
     pub fn is_in(&self, pos: Position) -> bool {
+        if let Some((lower, upper)) = self.bounds {
+            if pos.x < lower.x
+                || pos.y < lower.y
+                || pos.z < lower.z
+                || pos.x > upper.x
+                || pos.y > upper.y
+                || pos.z > upper.z
+            {
+                return false;
+            }
+        }
+        if let Some(cells) = &self.cells {
+            if !cells.contains(&pos) {
+                return false;
+            }
+        }
+        if self.excluded.contains(&pos) {
+            return false;
+        }
         // test all conditions
         for cond in &self.conditions {
             if !cond(pos) {
@@ -24,12 +112,99 @@ impl Area {
         true
     }
 
-    // TODO: add split condition
+    /*
+     * all cells of the area, ordered by x, y, z; an area without bounds
+     * is taken as empty here and in the set operations below
+     */
+    pub fn cells(&self) -> Vec<Position> {
+        let mut result: Vec<Position> = Vec::new();
+        if let Some((lower, upper)) = self.bounds {
+            for x in lower.x..=upper.x {
+                for y in lower.y..=upper.y {
+                    for z in lower.z..=upper.z {
+                        let pos = Position::new(x, y, z);
+                        if self.is_in(pos) {
+                            result.push(pos);
+                        }
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    pub fn volume(&self) -> Option<usize> {
+        self.bounds.map(|_| self.cells().len())
+    }
+
+    // area of the given cells on the lattice of self
+    fn with_cells(&self, cells: HashSet<Position>) -> Area {
+        let mut area = Area::new();
+        area.lattice = Rc::clone(&self.lattice);
+        area.colours = self.colours.clone();
+        let mut it = cells.iter();
+        if let Some(first) = it.next() {
+            let (mut lower, mut upper) = (*first, *first);
+            for pos in it {
+                lower = Position::new(lower.x.min(pos.x), lower.y.min(pos.y), lower.z.min(pos.z));
+                upper = Position::new(upper.x.max(pos.x), upper.y.max(pos.y), upper.z.max(pos.z));
+            }
+            area.bounds = Some((lower, upper));
+        } else {
+            // nothing is in an empty box
+            area.bounds = Some((Position::new(0, 0, 0), Position::new(-1, -1, -1)));
+        }
+        area.cells = Some(cells);
+        area
+    }
+
+    pub fn union(&self, other: &Area) -> Area {
+        let mut cells: HashSet<Position> = self.cells().into_iter().collect();
+        cells.extend(other.cells());
+        let mut area = self.with_cells(cells);
+        for (pos, colour) in &other.colours {
+            area.colours.entry(*pos).or_insert(*colour);
+        }
+        area
+    }
+
+    pub fn intersection(&self, other: &Area) -> Area {
+        let cells = self.cells().into_iter().filter(|pos| other.is_in(*pos));
+        self.with_cells(cells.collect())
+    }
+
+    pub fn difference(&self, other: &Area) -> Area {
+        let cells = self.cells().into_iter().filter(|pos| !other.is_in(*pos));
+        self.with_cells(cells.collect())
+    }
+
+    pub fn colour(&self, pos: Position) -> Option<char> {
+        self.colours.get(&pos).cloned()
+    }
+
+    // colours cells alternately like a checkerboard, painted cells are kept
+    pub fn checker(&mut self, even: char, odd: char) {
+        for pos in self.cells() {
+            let parity = (pos.x as i32 + pos.y as i32 + pos.z as i32).rem_euclid(2);
+            self.colours
+                .entry(pos)
+                .or_insert(if parity == 0 { even } else { odd });
+        }
+    }
+
+    // plain box, nothing cut out
+    pub fn is_box(&self) -> bool {
+        self.bounds.is_some()
+            && self.cells.is_none()
+            && self.excluded.is_empty()
+            && self.conditions.is_empty()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::snake::brick::Orientation;
 
     #[test]
     fn test_area() {
@@ -44,4 +219,73 @@ mod tests {
         assert!(!area.is_in(b));
         assert!(!area.is_in(c));
     }
+
+    #[test]
+    fn test_cuboid() {
+        let area = Area::cuboid(3, 2, 1);
+        assert!(area.is_in(Position::new(0, 0, 0)));
+        assert!(area.is_in(Position::new(2, 1, 0)));
+        assert!(!area.is_in(Position::new(3, 1, 0)));
+        assert!(!area.is_in(Position::new(2, 2, 0)));
+        assert!(!area.is_in(Position::new(0, 0, -1)));
+
+        let area = Area::cuboid(32, 32, 32);
+        assert_eq!(Some(32768), area.volume());
+        assert!(area.is_in(Position::new(31, 31, 31)));
+        assert!(!area.is_in(Position::new(32, 0, 0)));
+
+        let area = Area::cuboid(0, 0, 0);
+        assert_eq!(Some(0), area.volume());
+        assert!(!area.is_in(Position::new(0, 0, 0)));
+    }
+
+    #[test]
+    fn test_planar() {
+        let area = Area::rect(3, 2);
+        assert!(area.is_in(Position::new(1, 2, 0)));
+        assert!(!area.is_in(Position::new(2, 1, 0)));
+        assert!(!area.is_in(Position::new(0, 0, 1)));
+        assert!(!area.lattice.has(Position::new(0, 0, 0), Orientation::Up));
+
+        let area = Area::bitmap(&["##.", "#.#"]);
+        assert!(area.is_in(Position::new(1, 0, 0)));
+        assert!(area.is_in(Position::new(1, 1, 0)));
+        assert!(!area.is_in(Position::new(1, 2, 0)));
+        assert!(area.is_in(Position::new(0, 2, 0)));
+        assert!(!area.is_in(Position::new(0, 1, 0)));
+    }
+
+    #[test]
+    fn test_operations() {
+        let mut area = Area::cuboid(3, 3, 3);
+        assert_eq!(Some(27), area.volume());
+        assert!(area.is_box());
+        area.excluded.insert(Position::new(1, 1, 1));
+        assert!(!area.is_in(Position::new(1, 1, 1)));
+        assert_eq!(Some(26), area.volume());
+        assert!(!area.is_box());
+
+        let bar = Area::span(Position::new(1, 5, 1), Position::new(1, -5, 1));
+        assert_eq!(Some(11), bar.volume());
+        let union = area.union(&bar);
+        assert_eq!(Some(35), union.volume());
+        assert!(union.is_in(Position::new(1, 1, 1)));
+        assert!(union.is_in(Position::new(1, -5, 1)));
+        assert_eq!(Some(2), area.intersection(&bar).volume());
+        let difference = area.difference(&bar);
+        assert_eq!(Some(24), difference.volume());
+        assert!(!difference.is_in(Position::new(1, 0, 1)));
+        assert!(difference.is_in(Position::new(0, 0, 1)));
+
+        assert_eq!(Some(0), bar.difference(&bar).volume());
+
+        let mut area = Area::cuboid(2, 2, 1);
+        area.checker('w', 'k');
+        assert_eq!(Some('w'), area.colour(Position::new(1, 1, 0)));
+        assert_eq!(Some('k'), area.colour(Position::new(0, 1, 0)));
+        assert_eq!(None, area.colour(Position::new(0, 2, 0)));
+        let union = area.union(&Area::span(Position::new(0, 2, 0), Position::new(0, 2, 0)));
+        assert_eq!(Some('k'), union.colour(Position::new(1, 0, 0)));
+        assert_eq!(None, Area::new().volume());
+    }
 }
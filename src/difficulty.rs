@@ -0,0 +1,128 @@
+use crate::path::Path;
+
+/*
+ * how hard a puzzle is for the solver, taken from the statistics a
+ * folded path has recorded
+ */
+#[derive(Debug, PartialEq)]
+pub struct Difficulty {
+    // partial paths built over all steps
+    pub nodes: usize,
+    // widest layer and the step it occurs at
+    pub peak: usize,
+    pub peak_step: usize,
+    // step at which most partial paths have no valid successor
+    pub dying_step: usize,
+    pub dying: usize,
+    pub solutions: usize,
+    // log2 of the partial paths explored per solution
+    pub score: f64,
+}
+
+impl Difficulty {
+    pub fn new(path: &Path) -> Difficulty {
+        let mut nodes = 0;
+        let mut peak = (0, 0);
+        for (step, nn) in &path.statistics {
+            nodes += nn;
+            if *nn > peak.1 {
+                peak = (*step, *nn);
+            }
+        }
+        let mut dying = (0, 0);
+        for (step, nn) in &path.dead_ends {
+            if *nn > dying.1 {
+                dying = (*step, *nn);
+            }
+        }
+        let solutions = if path.is_complete() {
+            path.layer_size()
+        } else {
+            0
+        };
+
+        Difficulty {
+            nodes,
+            peak: peak.1,
+            peak_step: peak.0 + 1,
+            dying: dying.1,
+            dying_step: dying.0 + 1,
+            solutions,
+            score: (nodes.max(1) as f64 / solutions.max(1) as f64).log2(),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut output = "difficulty\n".to_string();
+        output.push_str(&format!("nodes explored\t{}\n", self.nodes));
+        output.push_str(&format!(
+            "peak frontier\t{} (step {})\n",
+            self.peak, self.peak_step
+        ));
+        output.push_str(&format!(
+            "most dead ends\t{} (step {})\n",
+            self.dying, self.dying_step
+        ));
+        output.push_str(&format!("solutions\t{}\n", self.solutions));
+        output.push_str(&format!("score\t{:.2}\n", self.score));
+        output
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"nodes\": {}, \"peak\": {}, \"peak_step\": {}, \"dying\": {}, \"dying_step\": {}, \"solutions\": {}, \"score\": {:.2}}}\n",
+            self.nodes,
+            self.peak,
+            self.peak_step,
+            self.dying,
+            self.dying_step,
+            self.solutions,
+            self.score
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_difficulty() {
+        let mut parser = Parser::new(
+            "area cube 3
+chain 27 SSTTTSTTSTTTSTSTTTTSTSTSTSS
+path NE
+start 0 0 0
+",
+        );
+        parser.path.fold(false);
+        let diff = Difficulty::new(&parser.path);
+        // see the statistics of this puzzle
+        assert_eq!(227, diff.nodes);
+        assert_eq!(32, diff.peak);
+        assert_eq!(8, diff.peak_step);
+        assert_eq!(1, diff.solutions);
+        assert!(diff.dying > 0);
+        assert!((diff.score - 227f64.log2()).abs() < 1e-9);
+
+        assert!(diff.to_text().contains("peak frontier\t32 (step 8)\n"));
+        assert!(diff
+            .to_json()
+            .starts_with("{\"nodes\": 227, \"peak\": 32, \"peak_step\": 8, "));
+    }
+
+    #[test]
+    fn test_unsolvable() {
+        let mut parser = Parser::new(
+            "area cube 2
+chain 8 SSSTTTTT
+path N
+start 0 0 0
+",
+        );
+        parser.path.fold(false);
+        let diff = Difficulty::new(&parser.path);
+        assert_eq!(0, diff.solutions);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::area::{Area, Coord, Position};
+use crate::generator::{bricks_from_cells, isolates};
+use crate::grid::Grid;
+use crate::lattice::Cubic;
+use crate::symmetry::{representative_cells, Symmetry};
+use std::collections::BTreeMap;
+use std::vec::Vec;
+
+/*
+ * walks all hamiltonian paths of a box; every path is counted once up
+ * to symmetries of the box and reversal, namely in the orientation
+ * where its sequence of cells is the lexicographically smallest
+ */
+pub struct Enumeration {
+    dims: (Coord, Coord, Coord),
+    grid: Grid,
+    symmetries: Vec<Symmetry>,
+    // distinct chains (the smaller of chain and reversed chain) and
+    // their number of foldings
+    pub chains: BTreeMap<String, usize>,
+    pub paths: usize,
+}
+
+impl Enumeration {
+    pub fn new(dims: (Coord, Coord, Coord)) -> Enumeration {
+        Enumeration {
+            dims,
+            grid: Grid::new(&Area::cuboid(dims.0, dims.1, dims.2), usize::MAX).unwrap(),
+            symmetries: Symmetry::all(dims),
+            chains: BTreeMap::new(),
+            paths: 0,
+        }
+    }
+
+    pub fn run(&mut self) {
+        for start in representative_cells(self.dims, &self.symmetries) {
+            let mut visited = vec![false; self.grid.len()];
+            visited[self.grid.id(start).unwrap()] = true;
+            let mut cells = vec![start];
+            self.extend(&mut cells, &mut visited);
+        }
+    }
+
+    fn extend(&mut self, cells: &mut Vec<Position>, visited: &mut Vec<bool>) {
+        if cells.len() == visited.len() {
+            if self.is_canonical(cells) {
+                self.record(cells);
+            }
+            return;
+        }
+        let last = self.grid.id(*cells.last().unwrap()).unwrap();
+        for next in self.grid.neighbours(last).to_vec() {
+            if visited[next] {
+                continue;
+            }
+            visited[next] = true;
+            cells.push(self.grid.position(next));
+            if !isolates(&self.grid, last, next, visited) {
+                self.extend(cells, visited);
+            }
+            cells.pop();
+            visited[next] = false;
+        }
+    }
+
+    // no image of the path under symmetry or reversal is smaller
+    fn is_canonical(&self, cells: &[Position]) -> bool {
+        let nn = cells.len();
+        for sym in &self.symmetries {
+            for reversed in [false, true].iter() {
+                for ii in 0..nn {
+                    let pos = if *reversed {
+                        cells[nn - 1 - ii]
+                    } else {
+                        cells[ii]
+                    };
+                    let image = sym.position(pos);
+                    let key = (image.x, image.y, image.z);
+                    let own = (cells[ii].x, cells[ii].y, cells[ii].z);
+                    if key < own {
+                        return false;
+                    }
+                    if key > own {
+                        break;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    fn record(&mut self, cells: &[Position]) {
+        let chain: String = bricks_from_cells(&Cubic, cells)
+            .iter()
+            .map(|brk| brk.form.to_char())
+            .collect();
+        let reversed: String = chain.chars().rev().collect();
+        *self.chains.entry(chain.min(reversed)).or_insert(0) += 1;
+        self.paths += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enumerate() {
+        let mut enumeration = Enumeration::new((2, 2, 2));
+        enumeration.run();
+        // three shapes, all with the same chain
+        assert_eq!(3, enumeration.paths);
+        assert_eq!(Some(&3), enumeration.chains.get("STTTTTTS"));
+
+        let mut enumeration = Enumeration::new((3, 1, 1));
+        enumeration.run();
+        assert_eq!(1, enumeration.paths);
+        assert_eq!(Some(&1), enumeration.chains.get("SSS"));
+
+        let mut enumeration = Enumeration::new((2, 2, 3));
+        enumeration.run();
+        assert_eq!(41, enumeration.paths);
+        assert_eq!(17, enumeration.chains.len());
+        assert_eq!(Some(&14), enumeration.chains.get("STTTTTTTTTTS"));
+        assert_eq!(Some(&2), enumeration.chains.get("SSTTTTSTTTTS"));
+        assert_eq!(
+            enumeration.paths,
+            enumeration.chains.values().sum::<usize>()
+        );
+        for chain in enumeration.chains.keys() {
+            assert_eq!(12, chain.len());
+            let reversed: String = chain.chars().rev().collect();
+            assert!(*chain <= reversed);
+        }
+    }
+}
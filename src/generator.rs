@@ -0,0 +1,326 @@
+use crate::area::{Area, Position};
+use crate::grid::Grid;
+use crate::lattice::Lattice;
+use crate::random::Random;
+use crate::snake::brick::{Brick, Orientation};
+use crate::snake::chain::Form;
+use crate::verify::orientation_switches;
+use std::vec::Vec;
+
+/*
+ * builds random solvable puzzles: a random self-avoiding walk with
+ * backtracking through an area, covering every cell, turned into a
+ * chain; straight and turn mean what they mean on the lattice of the
+ * area
+ *
+ * segment lengths count the bricks of a straight piece including both
+ * corners, i.e. the classic 3x3x3 snake has segments of length 2 and 3
+ */
+pub struct Generator {
+    pub area: Area,
+    grid: Grid,
+    pub min_segment: usize,
+    pub max_segment: usize,
+    // expansions per walk before starting over
+    pub budget: usize,
+    rng: Random,
+}
+
+impl Generator {
+    // the input format only describes areas at non-negative coordinates
+    pub fn new(area: Area, rng: Random) -> Generator {
+        let (lower, upper) = area
+            .bounds
+            .expect("the generator needs an area with bounds");
+        assert!(
+            lower.x >= 0 && lower.y >= 0 && lower.z >= 0,
+            "the generator needs an area at non-negative coordinates"
+        );
+        let grid = Grid::new(&area, usize::MAX).unwrap();
+        let extent = (upper.x - lower.x + 1)
+            .max(upper.y - lower.y + 1)
+            .max(upper.z - lower.z + 1);
+        Generator {
+            area,
+            grid,
+            min_segment: 2,
+            max_segment: extent.max(1) as usize,
+            budget: 100_000,
+            rng,
+        }
+    }
+
+    // cells of a walk visiting every cell of the area once
+    pub fn walk(&mut self, attempts: usize) -> Option<Vec<Position>> {
+        if self.grid.len() == 0 {
+            return None;
+        }
+        for _ in 0..attempts {
+            let start = self.rng.below(self.grid.len());
+            let mut visited = vec![false; self.grid.len()];
+            visited[start] = true;
+            let mut cells = vec![start];
+            let mut expansions = 0;
+            if self.extend(&mut cells, &mut visited, None, 1, &mut expansions) {
+                return Some(cells.iter().map(|id| self.grid.position(*id)).collect());
+            }
+        }
+        None
+    }
+
+    fn extend(
+        &mut self,
+        cells: &mut Vec<usize>,
+        visited: &mut Vec<bool>,
+        dir: Option<Orientation>,
+        segment: usize,
+        expansions: &mut usize,
+    ) -> bool {
+        if cells.len() == visited.len() {
+            return segment >= self.min_segment;
+        }
+        *expansions += 1;
+        if *expansions > self.budget {
+            return false;
+        }
+
+        let last = *cells.last().unwrap();
+        let pos = self.grid.position(last);
+        let mut candidates: Vec<(usize, Orientation, usize)> = Vec::new();
+        for next in self.grid.neighbours(last) {
+            if visited[*next] {
+                continue;
+            }
+            let ori = orientation(pos, self.grid.position(*next));
+            match dir {
+                None => candidates.push((*next, ori, 2)),
+                Some(dd) => {
+                    let from = Brick::new(pos, dd, Form::Turn);
+                    if self.area.lattice.allows(&from, Form::Straight, ori) {
+                        if segment < self.max_segment {
+                            candidates.push((*next, ori, segment + 1));
+                        }
+                    } else if self.area.lattice.allows(&from, Form::Turn, ori)
+                        && segment >= self.min_segment
+                    {
+                        candidates.push((*next, ori, 2));
+                    }
+                }
+            }
+        }
+        self.rng.shuffle(&mut candidates);
+
+        for (next, ori, seg) in candidates {
+            visited[next] = true;
+            cells.push(next);
+            if !isolates(&self.grid, last, next, visited)
+                && self.extend(cells, visited, Some(ori), seg, expansions)
+            {
+                return true;
+            }
+            cells.pop();
+            visited[next] = false;
+        }
+        false
+    }
+
+    /*
+     * area lines for the input format: a box with the cells outside of
+     * the area excluded, unless it is a plain box
+     */
+    fn area_lines(&self) -> String {
+        let mut output = String::new();
+        let name = self.area.lattice.name();
+        if name != "cubic" {
+            output.push_str(&format!("lattice {}\n", name));
+        }
+        let (lower, upper) = self.area.bounds.unwrap();
+        let (x, y, z) = (upper.x + 1, upper.y + 1, upper.z + 1);
+        if self.area.is_box() && lower == Position::new(0, 0, 0) && x == y && y == z {
+            output.push_str(&format!("area cube {}\n", x));
+            return output;
+        }
+        output.push_str(&format!("area box {} {} {}\n", x, y, z));
+        for pos in Area::cuboid(x, y, z).cells() {
+            if !self.area.is_in(pos) {
+                output.push_str(&format!("exclude {} {} {}\n", pos.x, pos.y, pos.z));
+            }
+        }
+        output
+    }
+
+    // puzzle in the input format, with the walk as known solution
+    pub fn puzzle(&mut self, attempts: usize) -> Option<String> {
+        let cells = self.walk(attempts)?;
+        let bricks = bricks_from_cells(&*self.area.lattice, &cells);
+
+        let mut output = self.area_lines();
+        let chain: String = bricks.iter().map(|brk| brk.form.to_char()).collect();
+        output.push_str(&format!("chain {} {}\n", bricks.len(), chain));
+        let switches = orientation_switches(&bricks);
+        output.push_str(&format!(
+            "path {}\n",
+            switches.chars().take(2).collect::<String>()
+        ));
+        output.push_str(&format!(
+            "start {} {} {}\n",
+            cells[0].x, cells[0].y, cells[0].z
+        ));
+        output.push_str(&format!("solution {}\n", switches));
+        Some(output)
+    }
+}
+
+// orientation from a cell to a neighbour
+fn orientation(from: Position, to: Position) -> Orientation {
+    Orientation::from_vector((to.x - from.x, to.y - from.y, to.z - from.z))
+        .expect("cells are not adjacent")
+}
+
+/*
+ * leaving `last` towards `next` can cut off a free neighbour of `last`
+ * which has no other free neighbour, it could only be the very end of
+ * a walk through the grid then
+ */
+pub fn isolates(grid: &Grid, last: usize, next: usize, visited: &[bool]) -> bool {
+    let mut isolated = 0;
+    for nb in grid.neighbours(last) {
+        if visited[*nb] {
+            continue;
+        }
+        let free = grid
+            .neighbours(*nb)
+            .iter()
+            .filter(|nn| !visited[**nn] || **nn == next)
+            .count();
+        if free == 0 {
+            return true;
+        }
+        if free == 1 {
+            isolated += 1;
+        }
+    }
+    isolated > 1
+}
+
+/*
+ * every brick points to its successor, the last one keeps the
+ * orientation of its predecessor; a brick is straight where the
+ * lattice lets it keep the orientation of its predecessor
+ */
+pub fn bricks_from_cells(lattice: &dyn Lattice, cells: &[Position]) -> Vec<Brick> {
+    let mut bricks: Vec<Brick> = Vec::new();
+    for (ii, pos) in cells.iter().enumerate() {
+        let orientation = match cells.get(ii + 1) {
+            Some(next) => orientation(*pos, *next),
+            None => bricks
+                .last()
+                .map(|brk| brk.orientation)
+                .unwrap_or(Orientation::North),
+        };
+        let form = match bricks.last() {
+            Some(last) if !lattice.allows(last, Form::Straight, orientation) => Form::Turn,
+            _ => Form::Straight,
+        };
+        bricks.push(Brick::new(*pos, orientation, form));
+    }
+    bricks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lattice::{Prism, Square};
+    use crate::parser::Parser;
+    use crate::verify::verify;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_walk() {
+        let mut generator = Generator::new(Area::cuboid(3, 3, 3), Random::new(7));
+        generator.min_segment = 2;
+        generator.max_segment = 3;
+        let cells = generator.walk(100).unwrap();
+        assert_eq!(27, cells.len());
+
+        let bricks = bricks_from_cells(&*generator.area.lattice, &cells);
+        let switches = orientation_switches(&bricks);
+        let mut length = 1;
+        for pair in bricks.windows(2) {
+            if pair[1].orientation == pair[0].orientation {
+                length += 1;
+            } else {
+                assert!((2..=3).contains(&(length + 1)));
+                length = 1;
+            }
+        }
+        assert!(switches.len() > 1);
+    }
+
+    #[test]
+    fn test_puzzle() {
+        for seed in 0..5 {
+            let mut generator = Generator::new(Area::cuboid(3, 3, 2), Random::new(seed));
+            let puzzle = generator.puzzle(100).unwrap();
+            assert!(puzzle.starts_with("area box 3 3 2\nchain 18 "));
+
+            // the known folding is valid and found by the solver
+            let mut parser = Parser::new(&puzzle);
+            assert_eq!(1, parser.solutions.len());
+            assert_eq!(
+                Ok(()),
+                verify(
+                    &parser.path.area,
+                    &parser.path.chain,
+                    &parser.path.constraints,
+                    parser.start,
+                    parser.up,
+                    &parser.solutions[0]
+                )
+            );
+            assert!(parser.path.fold(false) > 0);
+            assert!(parser.path.is_complete());
+        }
+    }
+
+    #[test]
+    fn test_areas() {
+        let check = |area: Area, head: &str| {
+            let mut generator = Generator::new(area, Random::new(3));
+            let puzzle = generator.puzzle(100).unwrap();
+            assert!(puzzle.starts_with(head));
+            let mut parser = Parser::new(&puzzle);
+            assert_eq!(
+                Ok(()),
+                verify(
+                    &parser.path.area,
+                    &parser.path.chain,
+                    &parser.path.constraints,
+                    parser.start,
+                    parser.up,
+                    &parser.solutions[0]
+                )
+            );
+            parser.path.fold(false);
+            assert!(parser.path.is_complete());
+            puzzle
+        };
+
+        // a box with a hole, the cells outside of the area are excluded
+        let mut area = Area::cuboid(3, 3, 2);
+        area.excluded.insert(Position::new(1, 1, 1));
+        let puzzle = check(area, "area box 3 3 2\nexclude 1 1 1\nchain 17 ");
+        assert_eq!(1, puzzle.matches("exclude").count());
+
+        let mut area = Area::cuboid(4, 4, 1)
+            .intersection(&Area::span(Position::new(1, 1, 0), Position::new(3, 2, 0)));
+        area.lattice = Rc::new(Square);
+        let puzzle = check(area, "lattice square\narea box 4 3 1\n");
+        assert_eq!(6, puzzle.matches("exclude").count());
+
+        // six wedges around a vertex, turns only
+        let mut area = Area::cuboid(2, 3, 1);
+        area.lattice = Rc::new(Prism);
+        check(area, "lattice prism\narea box 2 3 1\nchain 6 STTTTT\n");
+    }
+}
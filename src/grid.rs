@@ -0,0 +1,133 @@
+use crate::area::{Area, Coord, Position};
+use std::vec::Vec;
+
+/*
+ * the cells of a bounded area numbered 0..len in the order of
+ * Area::cells, with the neighbours of every cell on the lattice of the
+ * area; the id of a position is looked up in a table over the bounding
+ * box
+ */
+pub struct Grid {
+    lower: Position,
+    dims: (usize, usize, usize),
+    // id of every cell of the bounding box, None if not in the area
+    ids: Vec<Option<usize>>,
+    positions: Vec<Position>,
+    neighbours: Vec<Vec<usize>>,
+}
+
+impl Grid {
+    /*
+     * None for areas without bounds and for bounding boxes of more than
+     * max_cells cells, before the table over the box is allocated
+     */
+    pub fn new(area: &Area, max_cells: usize) -> Option<Grid> {
+        let (lower, upper) = area.bounds?;
+        let extent = |aa: Coord, bb: Coord| (bb as i32 - aa as i32 + 1).max(0) as usize;
+        let dims = (
+            extent(lower.x, upper.x),
+            extent(lower.y, upper.y),
+            extent(lower.z, upper.z),
+        );
+        if dims.0 * dims.1 * dims.2 > max_cells {
+            return None;
+        }
+        let mut grid = Grid {
+            lower,
+            dims,
+            ids: Vec::new(),
+            positions: Vec::new(),
+            neighbours: Vec::new(),
+        };
+        grid.ids = vec![None; grid.dims.0 * grid.dims.1 * grid.dims.2];
+
+        let cells = area.cells();
+        for (id, pos) in cells.iter().enumerate() {
+            let offset = grid.offset(*pos).unwrap();
+            grid.ids[offset] = Some(id);
+        }
+        for pos in &cells {
+            let nbs = area
+                .lattice
+                .neighbours(*pos)
+                .iter()
+                .filter_map(|nb| grid.id(*nb))
+                .collect();
+            grid.neighbours.push(nbs);
+        }
+        grid.positions = cells;
+        Some(grid)
+    }
+
+    // index of pos within the bounding box
+    fn offset(&self, pos: Position) -> Option<usize> {
+        let x = pos.x as i32 - self.lower.x as i32;
+        let y = pos.y as i32 - self.lower.y as i32;
+        let z = pos.z as i32 - self.lower.z as i32;
+        if x < 0
+            || y < 0
+            || z < 0
+            || x as usize >= self.dims.0
+            || y as usize >= self.dims.1
+            || z as usize >= self.dims.2
+        {
+            return None;
+        }
+        Some((x as usize * self.dims.1 + y as usize) * self.dims.2 + z as usize)
+    }
+
+    pub fn id(&self, pos: Position) -> Option<usize> {
+        self.ids[self.offset(pos)?]
+    }
+
+    pub fn position(&self, id: usize) -> Position {
+        self.positions[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn neighbours(&self, id: usize) -> &[usize] {
+        &self.neighbours[id]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lattice::Square;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_grid() {
+        let mut area = Area::cuboid(3, 3, 3);
+        area.excluded.insert(Position::new(1, 1, 1));
+        let grid = Grid::new(&area, 27).unwrap();
+        assert_eq!(26, grid.len());
+        assert_eq!(Some(0), grid.id(Position::new(0, 0, 0)));
+        assert_eq!(None, grid.id(Position::new(1, 1, 1)));
+        assert_eq!(Some(25), grid.id(Position::new(2, 2, 2)));
+        assert_eq!(Position::new(2, 2, 2), grid.position(25));
+        assert_eq!(None, grid.id(Position::new(3, 0, 0)));
+        assert_eq!(None, grid.id(Position::new(0, -1, 0)));
+        // the centre of a face lost its neighbour in the middle
+        let face = grid.id(Position::new(1, 1, 0)).unwrap();
+        assert_eq!(4, grid.neighbours(face).len());
+        for id in 0..grid.len() {
+            for nb in grid.neighbours(id) {
+                assert!(grid.neighbours(*nb).contains(&id));
+            }
+        }
+
+        let edge = grid.id(Position::new(0, 0, 1)).unwrap();
+        assert_eq!(4, grid.neighbours(edge).len());
+
+        area.lattice = Rc::new(Square);
+        let grid = Grid::new(&area, 27).unwrap();
+        assert_eq!(2, grid.neighbours(edge).len());
+        assert!(Grid::new(&Area::new(), 27).is_none());
+        // the bounding box counts, not the cells
+        assert!(Grid::new(&area, 26).is_none());
+    }
+}
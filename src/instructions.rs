@@ -0,0 +1,143 @@
+use crate::snake::brick::{Brick, Orientation};
+use std::vec::Vec;
+
+/*
+ * twist of a segment around the axis of the previous segment, looking
+ * along the chain; 0 degrees means parallel to the segment before the
+ * previous one (zigzag)
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Twist {
+    Start,
+    FirstTurn,
+    Zero,
+    Clockwise,
+    CounterClockwise,
+    Half,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Step {
+    pub segment: usize,
+    pub first: usize,
+    pub last: usize,
+    pub twist: Twist,
+}
+
+// split a folding (ordered from start to end) into straight segments
+pub fn segments(bricks: &[Brick]) -> Vec<(usize, usize, Orientation)> {
+    let mut segs: Vec<(usize, usize, Orientation)> = Vec::new();
+    for (ii, brk) in bricks.iter().enumerate() {
+        match segs.last_mut() {
+            Some(seg) if seg.2 == brk.orientation => seg.1 = ii,
+            _ => segs.push((ii, ii, brk.orientation)),
+        }
+    }
+    segs
+}
+
+pub fn steps(bricks: &[Brick]) -> Vec<Step> {
+    let segs = segments(bricks);
+    let mut result: Vec<Step> = Vec::new();
+
+    for (kk, seg) in segs.iter().enumerate() {
+        let twist = match kk {
+            0 => Twist::Start,
+            1 => Twist::FirstTurn,
+            _ => {
+                let axis = segs[kk - 1].2;
+                let reference = segs[kk - 2].2;
+                if seg.2 == reference {
+                    Twist::Zero
+                } else if seg.2 == reference.opposite() {
+                    Twist::Half
+                } else if reference.cross(axis) == Some(seg.2) {
+                    // cross is taken in the left-handed frame of the coordinates
+                    Twist::Clockwise
+                } else {
+                    Twist::CounterClockwise
+                }
+            }
+        };
+        result.push(Step {
+            segment: kk + 1,
+            first: seg.0 + 1,
+            last: seg.1 + 1,
+            twist,
+        });
+    }
+    result
+}
+
+pub fn instructions(bricks: &[Brick]) -> String {
+    let mut output = "assembly instructions\n".to_string();
+    output.push_str("(twists are measured around the previous segment, looking along the chain)\n");
+    for (nn, step) in steps(bricks).iter().enumerate() {
+        let action = match step.twist {
+            Twist::Start => "hold it straight, this is the start".to_string(),
+            Twist::FirstTurn => format!("turn 90° away from segment {}", step.segment - 1),
+            Twist::Zero => format!(
+                "no rotation relative to segment {} (zigzag)",
+                step.segment - 1
+            ),
+            Twist::Clockwise => format!(
+                "rotate 90° clockwise relative to segment {}",
+                step.segment - 1
+            ),
+            Twist::CounterClockwise => format!(
+                "rotate 90° counterclockwise relative to segment {}",
+                step.segment - 1
+            ),
+            Twist::Half => format!("rotate 180° relative to segment {}", step.segment - 1),
+        };
+        output.push_str(&format!(
+            "{:>3}. segment {} (bricks {}-{}): {}\n",
+            nn + 1,
+            step.segment,
+            step.first,
+            step.last,
+            action
+        ));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::cube_folding;
+
+    #[test]
+    fn test_steps() {
+        let (_, bricks) = cube_folding();
+        let steps = steps(&bricks);
+        assert_eq!(17, steps.len());
+        assert_eq!(
+            Step {
+                segment: 1,
+                first: 1,
+                last: 2,
+                twist: Twist::Start
+            },
+            steps[0]
+        );
+        assert_eq!(Twist::FirstTurn, steps[1].twist);
+        // N, E, S: U-shape
+        assert_eq!(Twist::Half, steps[2].twist);
+        // E, S, U: looking south, east is on the left
+        assert_eq!(Twist::Clockwise, steps[3].twist);
+        // S, U, E: looking up from below with south ahead, east is on the right
+        assert_eq!(Twist::Clockwise, steps[4].twist);
+        // N, U, W
+        assert_eq!(Twist::Clockwise, steps[8].twist);
+        // E, D, N: looking down with east ahead, north is on the left
+        assert_eq!(Twist::CounterClockwise, steps[6].twist);
+        // U, E, D
+        assert_eq!(Twist::Half, steps[5].twist);
+        // D, N, U
+        assert_eq!(Twist::Half, steps[7].twist);
+        // W, S, E, D, E: zigzag
+        assert_eq!(Twist::Zero, steps[12].twist);
+        println!("{}", instructions(&bricks));
+    }
+}
@@ -0,0 +1,302 @@
+use crate::area::Position;
+use crate::snake::brick::{Brick, Orientation, ORIENTATIONS};
+use crate::snake::chain::Form;
+use std::rc::Rc;
+use std::vec::Vec;
+
+/*
+ * grid the chain is folded on: cells are addressed by integer positions
+ * and a brick leaves its cell towards one of the orientations the
+ * lattice offers there
+ */
+pub trait Lattice {
+    // as in the lattice line of the input
+    fn name(&self) -> &'static str;
+
+    // can a brick in pos point towards ori
+    fn has(&self, pos: Position, ori: Orientation) -> bool;
+
+    fn step(&self, pos: Position, ori: Orientation) -> Option<Position> {
+        if self.has(pos, ori) {
+            pos.step(ori)
+        } else {
+            None
+        }
+    }
+
+    // are rotations and reflections of a box symmetries of the lattice
+    fn has_box_symmetry(&self) -> bool {
+        true
+    }
+
+    fn neighbours(&self, pos: Position) -> Vec<Position> {
+        ORIENTATIONS
+            .iter()
+            .filter_map(|ori| self.step(pos, *ori))
+            .collect()
+    }
+
+    // may a brick with form frm follow `from` in orientation `outgoing`
+    fn allows(&self, from: &Brick, frm: Form, outgoing: Orientation) -> bool {
+        frm.allows(from, outgoing)
+    }
+
+    // the bricks with form frm that can follow brk
+    fn successors(&self, brk: &Brick, frm: Form) -> Vec<Brick> {
+        // bricks added up front may point where the lattice has no edge
+        if !self.has(brk.coordinates, brk.orientation) {
+            return Vec::new();
+        }
+        let mut next = match frm {
+            Form::Straight => brk.next_straight().into_iter().collect(),
+            Form::Turn => brk.next_turn(),
+            _ => brk.next(frm),
+        };
+        next.retain(|nb| self.has(nb.coordinates, nb.orientation));
+        next
+    }
+
+    /*
+     * can a brick `steps` bricks further along the chain be in `to`;
+     * may be too generous, but never wrong. Every step changes one
+     * coordinate by one, so only within the distance and with matching
+     * parity
+     */
+    fn reachable(&self, from: Position, to: Position, steps: usize) -> bool {
+        let distance = (to.x as i32 - from.x as i32).unsigned_abs()
+            + (to.y as i32 - from.y as i32).unsigned_abs()
+            + (to.z as i32 - from.z as i32).unsigned_abs();
+        let steps = steps as u32;
+        distance <= steps && (steps - distance).is_multiple_of(2)
+    }
+}
+
+pub struct Cubic;
+
+impl Lattice for Cubic {
+    fn name(&self) -> &'static str {
+        "cubic"
+    }
+
+    fn has(&self, _pos: Position, _ori: Orientation) -> bool {
+        true
+    }
+}
+
+/*
+ * layers of triangles stacked along z, i.e. wedge shaped cells; a layer
+ * is stored like a brick wall: east and west lead to the neighbours in
+ * the same row, the third edge of a triangle leads north if x + y is
+ * even and south otherwise. A wedge has no face opposite to a side
+ * face: a straight brick passes between the triangles of one column
+ * only, a turn leaves through any face but the one it came in by (by
+ * 60 degrees within a layer, by 90 degrees up or down); joints that
+ * need right angles within the layer do not fit into a wedge
+ */
+pub struct Prism;
+
+impl Lattice for Prism {
+    fn name(&self) -> &'static str {
+        "prism"
+    }
+
+    fn has_box_symmetry(&self) -> bool {
+        false
+    }
+
+    fn allows(&self, from: &Brick, frm: Form, outgoing: Orientation) -> bool {
+        let incoming = from.orientation;
+        let vertical = matches!(incoming, Orientation::Up | Orientation::Down);
+        match frm {
+            Form::Straight => vertical && outgoing == incoming,
+            Form::Turn => outgoing != incoming.opposite() && !(vertical && outgoing == incoming),
+            Form::Free => outgoing != incoming.opposite(),
+            _ => false,
+        }
+    }
+
+    fn successors(&self, brk: &Brick, frm: Form) -> Vec<Brick> {
+        if !self.has(brk.coordinates, brk.orientation) {
+            return Vec::new();
+        }
+        ORIENTATIONS
+            .iter()
+            .filter(|ori| self.allows(brk, frm, **ori))
+            .filter_map(|ori| brk.next_orientation(ori, frm))
+            .filter(|nb| self.has(nb.coordinates, nb.orientation))
+            .collect()
+    }
+
+    fn has(&self, pos: Position, ori: Orientation) -> bool {
+        let even = (pos.x as i32 + pos.y as i32) % 2 == 0;
+        match ori {
+            Orientation::North => even,
+            Orientation::South => !even,
+            _ => true,
+        }
+    }
+
+    // two steps along x need a step along y in between
+    fn reachable(&self, from: Position, to: Position, steps: usize) -> bool {
+        let dx = (to.x as i32 - from.x as i32).unsigned_abs();
+        let dy = (to.y as i32 - from.y as i32).unsigned_abs();
+        let dz = (to.z as i32 - from.z as i32).unsigned_abs();
+        // the parity is the one of the cubic lattice
+        dx + dy.max(dx.saturating_sub(1)) + dz <= steps as u32 && Cubic.reachable(from, to, steps)
+    }
+}
+
+// a single layer, bricks never point up or down
+pub struct Square;
+
+impl Lattice for Square {
+    fn name(&self) -> &'static str {
+        "square"
+    }
+
+    fn has(&self, _pos: Position, ori: Orientation) -> bool {
+        !matches!(ori, Orientation::Up | Orientation::Down)
+    }
+}
+
+pub fn from_name(name: &str) -> Option<Rc<dyn Lattice>> {
+    match name {
+        "cubic" => Some(Rc::new(Cubic)),
+        "prism" => Some(Rc::new(Prism)),
+        "square" => Some(Rc::new(Square)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lattices() {
+        let pos = Position::new(1, 1, 0);
+        assert_eq!(6, Cubic.neighbours(pos).len());
+        assert_eq!(5, Prism.neighbours(pos).len());
+        assert_eq!(4, Square.neighbours(pos).len());
+        assert_eq!(
+            Some(Position::new(2, 1, 0)),
+            Prism.step(pos, Orientation::North)
+        );
+        assert_eq!(None, Prism.step(pos, Orientation::South));
+
+        // adjacency is symmetric
+        for x in -2..3 {
+            for y in -2..3 {
+                let pos = Position::new(x, y, 1);
+                for nb in Prism.neighbours(pos) {
+                    assert!(Prism.neighbours(nb).contains(&pos));
+                }
+            }
+        }
+        assert!(!from_name("prism").unwrap().has(pos, Orientation::South));
+        assert!(from_name("hexagonal").is_none());
+        for name in ["cubic", "prism", "square"].iter() {
+            assert_eq!(*name, from_name(name).unwrap().name());
+        }
+    }
+
+    #[test]
+    fn test_reachable() {
+        // every cell within a few steps, found by a breadth first search
+        let lattices: [&dyn Lattice; 3] = [&Cubic, &Prism, &Square];
+        for lattice in lattices.iter() {
+            let from = Position::new(0, 1, 0);
+            let mut layer = vec![from];
+            for steps in 1..7 {
+                layer = layer
+                    .iter()
+                    .flat_map(|pos| lattice.neighbours(*pos))
+                    .collect();
+                for pos in &layer {
+                    assert!(lattice.reachable(from, *pos, steps));
+                }
+            }
+        }
+        assert!(Cubic.reachable(Position::new(0, 0, 0), Position::new(3, 0, 0), 3));
+        assert!(!Prism.reachable(Position::new(0, 0, 0), Position::new(3, 0, 0), 3));
+        assert!(Prism.reachable(Position::new(0, 0, 0), Position::new(3, 0, 0), 5));
+    }
+
+    #[test]
+    fn test_prism() {
+        use crate::parser::Parser;
+        use crate::verify::{parse_orientations, verify, Violation};
+
+        // six wedges around a vertex, a ring of turns only; the cubic
+        // lattice needs straight bricks on the long sides of the box
+        let input = "lattice prism
+area box 2 3 1
+chain 6 TTTTTT cyclic
+path E
+start 0 0 0
+";
+        let verify_input = |input: &str, switches: &str| {
+            let parser = Parser::new(input);
+            verify(
+                &parser.path.area,
+                &parser.path.chain,
+                &parser.path.constraints,
+                parser.start,
+                None,
+                &parse_orientations(switches),
+            )
+        };
+        assert_eq!(Ok(()), verify_input(input, "EENWWS"));
+        assert_eq!(
+            Err(Violation::Turn(1)),
+            verify_input(&input.replace("lattice prism\n", ""), "EENWWS")
+        );
+        assert_eq!(
+            Ok(()),
+            verify_input(
+                &input
+                    .replace("lattice prism\n", "")
+                    .replace("TTTTTT", "TSTTST"),
+                "ENWS"
+            )
+        );
+        let mut parser = Parser::new(input);
+        assert_eq!(1, parser.path.fold(false));
+        assert_eq!(
+            "EENWWS",
+            parser.path.solutions(false).next().unwrap().orientations()
+        );
+
+        // no straight brick within a layer, only between the layers
+        let mut parser = Parser::new(&input.replace("TTTTTT", "TSTTST"));
+        assert_eq!(0, parser.path.fold(false));
+        let brick = |ori| Brick::new(Position::new(0, 0, 0), ori, Form::Turn);
+        assert!(Prism.allows(&brick(Orientation::Up), Form::Straight, Orientation::Up));
+        assert!(!Prism.allows(&brick(Orientation::East), Form::Straight, Orientation::East));
+        assert!(Prism.allows(&brick(Orientation::East), Form::Turn, Orientation::East));
+        assert!(!Prism.allows(&brick(Orientation::Up), Form::Turn, Orientation::Up));
+        assert!(!Prism.allows(&brick(Orientation::Up), Form::Planar, Orientation::North));
+    }
+
+    #[test]
+    fn test_square() {
+        use crate::parser::Parser;
+
+        // the last brick turns, but not out of the layer
+        let mut parser = Parser::new(
+            "area rect 2 2
+chain 4 STTT
+path N
+start 0 0 0
+",
+        );
+        assert_eq!(2, parser.path.fold(false));
+        for solution in parser.path.solutions(false) {
+            assert!(solution.orientations().starts_with("NES"));
+            assert!(solution
+                .bricks
+                .iter()
+                .all(|brk| Square.has(brk.coordinates, brk.orientation)));
+        }
+    }
+}
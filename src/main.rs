@@ -1,20 +1,309 @@
 mod area;
+mod difficulty;
+mod enumerate;
+mod generator;
+mod grid;
+mod instructions;
+mod lattice;
+mod mesh;
+mod packing;
 mod parser;
 mod path;
+mod random;
+mod render;
 mod snake;
+mod solution;
+mod svg;
+mod symmetry;
+#[cfg(test)]
+mod test_support;
 mod tree;
+mod unique;
+mod verify;
+mod viewer;
 
 use crate::parser::Parser;
+use crate::snake::brick::Coord;
+use crate::solution::Solution;
 use std::env;
 use std::fs;
 use std::fs::File;
+use std::io;
 use std::io::prelude::*;
 use std::io::Result;
 
+fn usage() {
+    println!("usage: cubesnake <in file> <out file>");
+    println!("       cubesnake solve <in file> <solution file> [--distinct]");
+    println!("       cubesnake verify <in file> [orientation switches]");
+    println!("       cubesnake instructions <in file>");
+    println!("       cubesnake render <in file>");
+    println!("       cubesnake svg <in file> <svg file> [solution number]");
+    println!("       cubesnake mesh <in file> <obj|stl|gltf file> [solution number] [joints]");
+    println!("       cubesnake view <in file> [solution number]");
+    println!(
+        "       cubesnake generate [--size N|XxYxZ | --area file] [--seed S] [--min L] [--max L] [--out file]"
+    );
+    println!("       cubesnake unique <in file>");
+    println!("       cubesnake difficulty <in file> [--json]");
+    println!("       cubesnake enumerate [--size N|XxYxZ]");
+    println!("       cubesnake pack <in file> [number of packings]");
+    println!(
+        "       cubesnake design [--size N|XxYxZ | --area file] [--seed S] [--min L] [--max L] [--tries T]"
+    );
+}
+
+fn generator_from_args(args: &[String]) -> generator::Generator {
+    // the area (and lattice) of a puzzle file, or else a box
+    let area = match option(args, "--area") {
+        Some(file) => {
+            let contents: String =
+                fs::read_to_string(file).expect("Something went wrong reading the file");
+            Parser::new(&contents).path.area
+        }
+        None => {
+            let size = parse_size(option(args, "--size").unwrap_or("3"));
+            area::Area::cuboid(size.0, size.1, size.2)
+        }
+    };
+    let rng = match option(args, "--seed") {
+        Some(seed) => random::Random::new(seed.parse().expect("invalid seed")),
+        None => random::Random::from_time(),
+    };
+    let mut gen = generator::Generator::new(area, rng);
+    if let Some(min) = option(args, "--min") {
+        gen.min_segment = min.parse().expect("invalid minimal segment length");
+    }
+    if let Some(max) = option(args, "--max") {
+        gen.max_segment = max.parse().expect("invalid maximal segment length");
+    }
+    gen
+}
+
+// value following a command line option
+fn option<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|ii| args.get(ii + 1))
+        .map(|arg| arg.as_str())
+}
+
+fn parse_size(size: &str) -> (Coord, Coord, Coord) {
+    let dims: Vec<Coord> = size
+        .split('x')
+        // a box needs at least one cell along every side
+        .map(|dd| dd.parse().ok().filter(|dd| *dd > 0).expect("invalid size"))
+        .collect();
+    match dims.len() {
+        1 => (dims[0], dims[0], dims[0]),
+        3 => (dims[0], dims[1], dims[2]),
+        _ => panic!("invalid size {}", size),
+    }
+}
+
+// valid solutions stored in the file, or folded if there are none
+fn solved(prsr: &mut Parser) -> Vec<Solution> {
+    if prsr.solutions.is_empty() {
+        prsr.path.fold(false);
+        return prsr.path.solutions(false).collect();
+    }
+    let mut result: Vec<Solution> = Vec::new();
+    for (ii, stored) in prsr.stored_solutions().into_iter().enumerate() {
+        match stored {
+            Ok(solution) => result.push(solution),
+            Err(violation) => println!("invalid solution {}: {}", ii + 1, violation),
+        }
+    }
+    result
+}
+
 fn main() -> Result<()> {
     let args: Vec<String> = env::args().collect();
 
-    if args.len() == 3 {
+    if args.len() >= 3 && args[1] == "verify" {
+        let contents: String =
+            fs::read_to_string(&args[2]).expect("Something went wrong reading the file");
+        let prsr = Parser::new(&contents);
+
+        // without explicit switches the solutions or the path of the
+        // input file are checked
+        let candidates = match args.get(3) {
+            Some(switches) => vec![verify::parse_orientations(switches)],
+            None if !prsr.solutions.is_empty() => prsr.solutions.to_vec(),
+            None => vec![prsr.orientations.to_vec()],
+        };
+        for orientations in &candidates {
+            match verify::verify(
+                &prsr.path.area,
+                &prsr.path.chain,
+                &prsr.path.constraints,
+                prsr.start,
+                prsr.up,
+                orientations,
+            ) {
+                Ok(()) => println!("valid folding"),
+                Err(violation) => println!("invalid folding: {}", violation),
+            }
+        }
+    } else if args.len() >= 4 && args[1] == "solve" {
+        let contents: String =
+            fs::read_to_string(&args[2]).expect("Something went wrong reading the file");
+        let mut prsr = Parser::new(&contents);
+
+        prsr.path.fold(false);
+
+        let distinct = args.iter().any(|arg| arg == "--distinct");
+        File::create(&args[3])?.write_all(prsr.solution_output(distinct).as_bytes())?;
+    } else if args.len() == 3 && args[1] == "instructions" {
+        let contents: String =
+            fs::read_to_string(&args[2]).expect("Something went wrong reading the file");
+        let mut prsr = Parser::new(&contents);
+
+        for solution in solved(&mut prsr) {
+            println!("{}", instructions::instructions(&solution.bricks));
+        }
+    } else if args.len() == 3 && args[1] == "render" {
+        let contents: String =
+            fs::read_to_string(&args[2]).expect("Something went wrong reading the file");
+        let mut prsr = Parser::new(&contents);
+
+        for solution in solved(&mut prsr) {
+            println!(
+                "{}",
+                render::layers(&prsr.path.area, &prsr.path.chain, &solution.bricks)
+            );
+        }
+    } else if args.len() >= 4 && args[1] == "svg" {
+        let contents: String =
+            fs::read_to_string(&args[2]).expect("Something went wrong reading the file");
+        let mut prsr = Parser::new(&contents);
+
+        let number: usize = match args.get(4) {
+            Some(nn) => nn.parse().expect("invalid solution number"),
+            None => 1,
+        };
+        match solved(&mut prsr).get(number.max(1) - 1) {
+            Some(solution) => File::create(&args[3])?
+                .write_all(svg::solution_svg(&prsr.path.chain, &solution.bricks).as_bytes())?,
+            None => println!("no solution number {}", number),
+        }
+    } else if args.len() >= 4 && args[1] == "mesh" {
+        let contents: String =
+            fs::read_to_string(&args[2]).expect("Something went wrong reading the file");
+        let mut prsr = Parser::new(&contents);
+
+        let number: usize = match args.get(4) {
+            Some(nn) => nn.parse().expect("invalid solution number"),
+            None => 1,
+        };
+        let joints = args.get(5).map(|arg| arg == "joints").unwrap_or(false);
+        match solved(&mut prsr).get(number.max(1) - 1) {
+            Some(solution) => {
+                let mesh = mesh::Mesh::from_bricks(&solution.bricks, joints);
+                let output = if args[3].ends_with(".obj") {
+                    mesh.to_obj()
+                } else if args[3].ends_with(".stl") {
+                    mesh.to_stl()
+                } else {
+                    mesh.to_gltf()
+                };
+                File::create(&args[3])?.write_all(output.as_bytes())?;
+            }
+            None => println!("no solution number {}", number),
+        }
+    } else if args.len() >= 3 && args[1] == "view" {
+        let contents: String =
+            fs::read_to_string(&args[2]).expect("Something went wrong reading the file");
+        let mut prsr = Parser::new(&contents);
+
+        let number: usize = match args.get(3) {
+            Some(nn) => nn.parse().expect("invalid solution number"),
+            None => 1,
+        };
+        match solved(&mut prsr).into_iter().nth(number.max(1) - 1) {
+            Some(solution) => {
+                let stdin = io::stdin();
+                let stdout = io::stdout();
+                viewer::Viewer::new(&prsr.path.area, solution)
+                    .run(stdin.lock(), &mut stdout.lock())?;
+            }
+            None => println!("no solution number {}", number),
+        }
+    } else if args.len() >= 2 && args[1] == "generate" {
+        let mut gen = generator_from_args(&args);
+        match gen.puzzle(1000) {
+            Some(puzzle) => match option(&args, "--out") {
+                Some(out) => File::create(out)?.write_all(puzzle.as_bytes())?,
+                None => print!("{}", puzzle),
+            },
+            None => println!("no chain found, try other segment lengths"),
+        }
+    } else if args.len() == 3 && args[1] == "unique" {
+        let contents: String =
+            fs::read_to_string(&args[2]).expect("Something went wrong reading the file");
+        let prsr = Parser::new(&contents);
+
+        let cns = unique::census(&prsr.path.area, &prsr.path.chain, &prsr.path.constraints, 0);
+        println!("solutions up to symmetry: {}", cns.solutions.len());
+        println!("nodes expanded: {}", cns.nodes);
+        println!("peak layer size: {}", cns.peak);
+    } else if args.len() >= 3 && args[1] == "pack" {
+        let contents: String =
+            fs::read_to_string(&args[2]).expect("Something went wrong reading the file");
+        let prsr = Parser::new(&contents);
+
+        let limit: usize = match args.get(3) {
+            Some(nn) => nn.parse().expect("invalid number of packings"),
+            None => 0,
+        };
+        let packings = packing::pack(&prsr.path.area, &prsr.snakes, &prsr.path.constraints, limit);
+        for (ii, bricks) in packings.iter().enumerate() {
+            println!("packing {}", ii + 1);
+            println!("{}", packing::packing_output(&prsr.path.area, bricks));
+        }
+        println!("packings found: {}", packings.len());
+    } else if args.len() >= 2 && args[1] == "design" {
+        let mut gen = generator_from_args(&args);
+        let tries = match option(&args, "--tries") {
+            Some(tries) => tries.parse().expect("invalid number of tries"),
+            None => 10,
+        };
+        let mut puzzles = unique::design(&mut gen, tries);
+        // hardest first
+        puzzles.sort_by_key(|(_, cns)| std::cmp::Reverse(cns.nodes));
+        for (puzzle, cns) in puzzles {
+            println!(
+                "# nodes expanded {}, peak layer size {}",
+                cns.nodes, cns.peak
+            );
+            println!("{}", puzzle);
+        }
+    } else if args.len() >= 3 && args[1] == "difficulty" {
+        let contents: String =
+            fs::read_to_string(&args[2]).expect("Something went wrong reading the file");
+        let mut prsr = Parser::new(&contents);
+
+        prsr.path.fold(false);
+        let diff = difficulty::Difficulty::new(&prsr.path);
+        if args.iter().any(|arg| arg == "--json") {
+            print!("{}", diff.to_json());
+        } else {
+            print!("{}", diff.to_text());
+        }
+    } else if args.len() >= 2 && args[1] == "enumerate" {
+        let size = parse_size(option(&args, "--size").unwrap_or("2"));
+        let mut enumeration = enumerate::Enumeration::new(size);
+        enumeration.run();
+        println!("chain foldings");
+        for (chain, count) in &enumeration.chains {
+            println!("{} {}", chain, count);
+        }
+        println!(
+            "{} chains, {} foldings up to symmetry",
+            enumeration.chains.len(),
+            enumeration.paths
+        );
+    } else if args.len() == 3 {
         let contents: String =
             fs::read_to_string(&args[1]).expect("Something went wrong reading the file");
 
@@ -26,7 +315,7 @@ fn main() -> Result<()> {
         File::create(&args[2])?.write_all(prsr.output().as_bytes())?;
     //println!("{}", prsr.output());
     } else {
-        println!("usage: cubesnake <in file> <out file>");
+        usage();
     }
     Ok(())
 }
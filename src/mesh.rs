@@ -0,0 +1,260 @@
+use crate::snake::brick::Brick;
+use std::vec::Vec;
+
+// gap between neighbouring cubes, makes the joints visible
+const GAP: f32 = 0.04;
+// edge length of the joint markers
+const JOINT: f32 = 0.2;
+
+pub struct Mesh {
+    pub vertices: Vec<[f32; 3]>,
+    pub triangles: Vec<[u32; 3]>,
+}
+
+impl Mesh {
+    pub fn new() -> Mesh {
+        Mesh {
+            vertices: Vec::new(),
+            triangles: Vec::new(),
+        }
+    }
+
+    // axis aligned box, triangles counterclockwise seen from outside
+    pub fn add_box(&mut self, lower: [f32; 3], upper: [f32; 3]) {
+        let base = self.vertices.len() as u32;
+        for ii in 0..8 {
+            self.vertices.push([
+                if ii & 1 == 0 { lower[0] } else { upper[0] },
+                if ii & 2 == 0 { lower[1] } else { upper[1] },
+                if ii & 4 == 0 { lower[2] } else { upper[2] },
+            ]);
+        }
+        let quads = [
+            [0, 2, 3, 1],
+            [4, 5, 7, 6],
+            [0, 1, 5, 4],
+            [2, 6, 7, 3],
+            [0, 4, 6, 2],
+            [1, 3, 7, 5],
+        ];
+        for qq in quads.iter() {
+            self.triangles
+                .push([base + qq[0], base + qq[1], base + qq[2]]);
+            self.triangles
+                .push([base + qq[0], base + qq[2], base + qq[3]]);
+        }
+    }
+
+    /*
+     * box given north, east and up; the mesh is right handed with y up
+     * as glTF expects it: x east, y up and z south
+     */
+    fn add_cells(&mut self, lower: [f32; 3], upper: [f32; 3]) {
+        self.add_box(
+            [lower[1], lower[2], -upper[0]],
+            [upper[1], upper[2], -lower[0]],
+        );
+    }
+
+    /*
+     * one cube per brick, optionally with a thin box marking the joint
+     * (the channel of the elastic cord) between consecutive bricks
+     */
+    pub fn from_bricks(bricks: &[Brick], joints: bool) -> Mesh {
+        let mut mesh = Mesh::new();
+        for brk in bricks {
+            let pos = brk.coordinates;
+            let lower = [f32::from(pos.x), f32::from(pos.y), f32::from(pos.z)];
+            mesh.add_cells(
+                [lower[0] + GAP, lower[1] + GAP, lower[2] + GAP],
+                [
+                    lower[0] + 1.0 - GAP,
+                    lower[1] + 1.0 - GAP,
+                    lower[2] + 1.0 - GAP,
+                ],
+            );
+        }
+        if joints {
+            for pair in bricks.windows(2) {
+                let aa = pair[0].coordinates;
+                let bb = pair[1].coordinates;
+                let ca = [f32::from(aa.x), f32::from(aa.y), f32::from(aa.z)];
+                let cb = [f32::from(bb.x), f32::from(bb.y), f32::from(bb.z)];
+                let mut lower = [0.0; 3];
+                let mut upper = [0.0; 3];
+                for kk in 0..3 {
+                    // from the centre of one cube to the centre of the next
+                    lower[kk] = ca[kk].min(cb[kk]) + 0.5 - JOINT / 2.0;
+                    upper[kk] = ca[kk].max(cb[kk]) + 0.5 + JOINT / 2.0;
+                }
+                mesh.add_cells(lower, upper);
+            }
+        }
+        mesh
+    }
+
+    fn normal(&self, tri: &[u32; 3]) -> [f32; 3] {
+        let aa = self.vertices[tri[0] as usize];
+        let bb = self.vertices[tri[1] as usize];
+        let cc = self.vertices[tri[2] as usize];
+        let uu = [bb[0] - aa[0], bb[1] - aa[1], bb[2] - aa[2]];
+        let vv = [cc[0] - aa[0], cc[1] - aa[1], cc[2] - aa[2]];
+        let nn = [
+            uu[1] * vv[2] - uu[2] * vv[1],
+            uu[2] * vv[0] - uu[0] * vv[2],
+            uu[0] * vv[1] - uu[1] * vv[0],
+        ];
+        let len = (nn[0] * nn[0] + nn[1] * nn[1] + nn[2] * nn[2]).sqrt();
+        [nn[0] / len, nn[1] / len, nn[2] / len]
+    }
+
+    pub fn to_obj(&self) -> String {
+        let mut output = "# cubesnake solution\n".to_string();
+        for vv in &self.vertices {
+            output.push_str(&format!("v {} {} {}\n", vv[0], vv[1], vv[2]));
+        }
+        for tri in &self.triangles {
+            // obj indices start at 1
+            output.push_str(&format!("f {} {} {}\n", tri[0] + 1, tri[1] + 1, tri[2] + 1));
+        }
+        output
+    }
+
+    pub fn to_stl(&self) -> String {
+        let mut output = "solid cubesnake\n".to_string();
+        for tri in &self.triangles {
+            let nn = self.normal(tri);
+            output.push_str(&format!("facet normal {} {} {}\n", nn[0], nn[1], nn[2]));
+            output.push_str("outer loop\n");
+            for idx in tri.iter() {
+                let vv = self.vertices[*idx as usize];
+                output.push_str(&format!("vertex {} {} {}\n", vv[0], vv[1], vv[2]));
+            }
+            output.push_str("endloop\nendfacet\n");
+        }
+        output.push_str("endsolid cubesnake\n");
+        output
+    }
+
+    /*
+     * self-contained gltf 2.0, the binary buffer (positions followed by
+     * indices) is embedded as base64 data uri
+     */
+    pub fn to_gltf(&self) -> String {
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut lower = [f32::INFINITY; 3];
+        let mut upper = [f32::NEG_INFINITY; 3];
+        for vv in &self.vertices {
+            for kk in 0..3 {
+                buffer.extend_from_slice(&vv[kk].to_le_bytes());
+                lower[kk] = lower[kk].min(vv[kk]);
+                upper[kk] = upper[kk].max(vv[kk]);
+            }
+        }
+        let positions_len = buffer.len();
+        for tri in &self.triangles {
+            for idx in tri.iter() {
+                buffer.extend_from_slice(&idx.to_le_bytes());
+            }
+        }
+        let indices_len = buffer.len() - positions_len;
+        // the bounds are optional, an empty mesh has none
+        let bounds = if self.vertices.is_empty() {
+            String::new()
+        } else {
+            format!(
+                ",\"min\":[{},{},{}],\"max\":[{},{},{}]",
+                lower[0], lower[1], lower[2], upper[0], upper[1], upper[2]
+            )
+        };
+
+        format!(
+            "{{\"asset\":{{\"version\":\"2.0\",\"generator\":\"cubesnake\"}},\
+\"scene\":0,\"scenes\":[{{\"nodes\":[0]}}],\"nodes\":[{{\"mesh\":0}}],\
+\"meshes\":[{{\"primitives\":[{{\"attributes\":{{\"POSITION\":0}},\"indices\":1}}]}}],\
+\"buffers\":[{{\"byteLength\":{},\"uri\":\"data:application/octet-stream;base64,{}\"}}],\
+\"bufferViews\":[{{\"buffer\":0,\"byteOffset\":0,\"byteLength\":{},\"target\":34962}},\
+{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{},\"target\":34963}}],\
+\"accessors\":[{{\"bufferView\":0,\"componentType\":5126,\"count\":{},\"type\":\"VEC3\"{}}},\
+{{\"bufferView\":1,\"componentType\":5125,\"count\":{},\"type\":\"SCALAR\"}}]}}\n",
+            buffer.len(),
+            base64(&buffer),
+            positions_len,
+            positions_len,
+            indices_len,
+            self.vertices.len(),
+            bounds,
+            3 * self.triangles.len()
+        )
+    }
+}
+
+fn base64(data: &[u8]) -> String {
+    let table = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut output = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let triple = (u32::from(bytes[0]) << 16) | (u32::from(bytes[1]) << 8) | u32::from(bytes[2]);
+        for kk in 0..4 {
+            if kk <= chunk.len() {
+                output.push(table[((triple >> (18 - 6 * kk)) & 63) as usize] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::cube_folding;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!("", base64(b""));
+        assert_eq!("Zg==", base64(b"f"));
+        assert_eq!("Zm8=", base64(b"fo"));
+        assert_eq!("Zm9v", base64(b"foo"));
+        assert_eq!("Zm9vYmFy", base64(b"foobar"));
+    }
+
+    #[test]
+    fn test_mesh() {
+        let (_, bricks) = cube_folding();
+
+        let mesh = Mesh::from_bricks(&bricks, false);
+        assert_eq!(8 * 27, mesh.vertices.len());
+        assert_eq!(12 * 27, mesh.triangles.len());
+        // outward normal of the north and the south face of the first cube
+        assert_eq!([0.0, 0.0, -1.0], mesh.normal(&mesh.triangles[0]));
+        assert_eq!([0.0, 0.0, 1.0], mesh.normal(&mesh.triangles[2]));
+        // the first cube is at the origin, the second one north of it
+        assert_eq!([GAP, GAP, GAP - 1.0], mesh.vertices[0]);
+        assert_eq!([1.0 - GAP, 1.0 - GAP, -GAP - 1.0], mesh.vertices[8 + 7]);
+
+        let mesh = Mesh::from_bricks(&bricks, true);
+        assert_eq!(12 * (27 + 26), mesh.triangles.len());
+
+        let obj = mesh.to_obj();
+        assert_eq!(8 * 53, obj.matches("\nv ").count());
+        assert_eq!(12 * 53, obj.matches("\nf ").count());
+        let stl = mesh.to_stl();
+        assert!(stl.starts_with("solid cubesnake\n"));
+        assert_eq!(12 * 53, stl.matches("facet normal").count());
+        let gltf = mesh.to_gltf();
+        assert!(gltf.contains("\"version\":\"2.0\""));
+        assert!(gltf.contains(&format!("\"byteLength\":{}", 53 * (8 * 12 + 36 * 4))));
+        assert!(gltf.contains("\"min\":[") && gltf.contains("\"max\":["));
+
+        // no bounds to give, but still valid json
+        let gltf = Mesh::new().to_gltf();
+        assert!(gltf.contains("\"count\":0,\"type\":\"VEC3\"}"));
+        assert!(!gltf.contains("inf"));
+    }
+}
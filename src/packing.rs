@@ -0,0 +1,304 @@
+use crate::area::{Area, Position};
+use crate::path::{Constraint, Path};
+use crate::snake::brick::{Brick, Orientation, ORIENTATIONS};
+use crate::snake::chain::{Chain, Form};
+use crate::solution::Solution;
+use std::vec::Vec;
+
+// one of the chains of a puzzle with its start options
+pub struct Snake {
+    pub chain: Chain,
+    pub start: Option<Position>,
+    // up of the first brick, for chains with handed bricks
+    pub up: Option<Orientation>,
+    // first orientation and those of the first turns
+    pub orientations: Vec<Orientation>,
+}
+
+impl Snake {
+    pub fn new(chain: Chain) -> Snake {
+        Snake {
+            chain,
+            start: None,
+            up: None,
+            orientations: Vec::new(),
+        }
+    }
+
+    // bricks given by the orientations, folded from start
+    pub fn prefix(&self, start: Position, orientations: &[Orientation]) -> Vec<Brick> {
+        let mut bricks: Vec<Brick> = Vec::new();
+        let mut ot = orientations.iter();
+
+        for frm in self.chain.dirs.iter() {
+            let brk = match bricks.last() {
+                None => match ot.next() {
+                    // no path given, nothing to start from
+                    None => break,
+                    Some(ori) => {
+                        let brk = Brick::new(start, *ori, *frm);
+                        Some(match self.up {
+                            Some(up) => brk.with_up(up),
+                            None => brk,
+                        })
+                    }
+                },
+                Some(nb) => match frm {
+                    Form::Straight => nb.next_straight(),
+                    _ => match ot.next() {
+                        None => break,
+                        Some(ori) => nb.next_orientation(ori, *frm),
+                    },
+                },
+            };
+            // the prefix ends at the end of the coordinate range
+            match brk {
+                Some(brk) => bricks.push(brk),
+                None => break,
+            }
+        }
+        bricks
+    }
+
+    /*
+     * first bricks to fold from within the free cells: as given, or else
+     * every free cell and every orientation
+     */
+    fn beginnings(&self, free: &Area) -> Vec<Vec<Brick>> {
+        let starts = match self.start {
+            Some(start) => vec![start],
+            None => free.cells(),
+        };
+        let mut result: Vec<Vec<Brick>> = Vec::new();
+        for start in starts {
+            if self.orientations.is_empty() {
+                for ori in ORIENTATIONS.iter() {
+                    result.push(self.prefix(start, &[*ori]));
+                }
+            } else {
+                result.push(self.prefix(start, &self.orientations));
+            }
+        }
+        result.retain(|bricks| {
+            bricks.iter().enumerate().all(|(ii, brk)| {
+                free.is_in(brk.coordinates)
+                    && bricks[..ii]
+                        .iter()
+                        .all(|other| other.coordinates != brk.coordinates)
+            })
+        });
+        result
+    }
+}
+
+/*
+ * places the snakes one after the other into the cells left free by the
+ * ones before, stops after `limit` packings (0: all); the constraints
+ * (fix and end lines) hold for the first snake, as when folding it alone
+ */
+pub fn pack(
+    area: &Area,
+    snakes: &[Snake],
+    constraints: &[Constraint],
+    limit: usize,
+) -> Vec<Vec<Solution>> {
+    let mut result: Vec<Vec<Solution>> = Vec::new();
+    place(
+        area,
+        snakes,
+        constraints,
+        &mut Vec::new(),
+        &mut result,
+        limit,
+    );
+    result
+}
+
+fn place(
+    area: &Area,
+    snakes: &[Snake],
+    constraints: &[Constraint],
+    placed: &mut Vec<Solution>,
+    result: &mut Vec<Vec<Solution>>,
+    limit: usize,
+) {
+    if placed.len() == snakes.len() {
+        result.push(placed.to_vec());
+        return;
+    }
+    let mut free = area.clone();
+    for solution in placed.iter() {
+        free.excluded.extend(solution.cells());
+    }
+    let remaining: usize = snakes[placed.len()..]
+        .iter()
+        .map(|snake| snake.chain.len())
+        .sum();
+    if free.volume().is_some_and(|volume| volume < remaining) {
+        return;
+    }
+
+    let snake = &snakes[placed.len()];
+    for beginning in snake.beginnings(&free) {
+        let mut path = Path::new(free.clone(), snake.chain.copy());
+        if placed.is_empty() {
+            path.constraints = constraints.to_vec();
+        }
+        for brk in &beginning {
+            path.add_brick(brk);
+        }
+        path.fold(false);
+        for solution in path.solutions(false) {
+            placed.push(solution);
+            place(area, snakes, constraints, placed, result, limit);
+            placed.pop();
+            if limit > 0 && result.len() >= limit {
+                return;
+            }
+        }
+    }
+}
+
+fn label(index: usize) -> char {
+    (b'A' + (index % 26) as u8) as char
+}
+
+/*
+ * every chain with its start and orientation switches, then the layers
+ * with the chain letter and brick number in every cell
+ */
+pub fn packing_output(area: &Area, packing: &[Solution]) -> String {
+    let mut output = String::new();
+    for (ii, solution) in packing.iter().enumerate() {
+        let start = solution.bricks[0].coordinates;
+        output.push_str(&format!(
+            "chain {} start {} {} {} path {}\n",
+            label(ii),
+            start.x,
+            start.y,
+            start.z,
+            solution.orientations()
+        ));
+    }
+    let (lower, upper) = match area.bounds {
+        None => return output,
+        Some(bb) => bb,
+    };
+    let cells: Vec<(Position, String)> = packing
+        .iter()
+        .enumerate()
+        .flat_map(|(ii, solution)| {
+            solution
+                .bricks
+                .iter()
+                .enumerate()
+                .map(move |(jj, brk)| (brk.coordinates, format!("{}{}", label(ii), jj + 1)))
+        })
+        .collect();
+    let width = cells.iter().map(|(_, cell)| cell.len()).max().unwrap_or(1) + 1;
+    for z in lower.z..=upper.z {
+        output.push_str(&format!("layer z = {}\n", z));
+        for x in (lower.x..=upper.x).rev() {
+            for y in lower.y..=upper.y {
+                let pos = Position::new(x, y, z);
+                let cell = match cells.iter().find(|(cc, _)| *cc == pos) {
+                    Some((_, cell)) => cell.to_string(),
+                    None if area.is_in(pos) => ".".to_string(),
+                    None => String::new(),
+                };
+                output.push_str(&format!("{:>w$}", cell, w = width));
+            }
+            output.push('\n');
+        }
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_pack() {
+        // two squares of four bricks stacked into a 2x2x2 cube
+        let parser = Parser::new(
+            "area cube 2
+chain 4 STTS
+start 0 0 0
+path NES
+chain 4 STTS
+",
+        );
+        assert_eq!(2, parser.snakes.len());
+        let packings = pack(
+            &parser.path.area,
+            &parser.snakes,
+            &parser.path.constraints,
+            0,
+        );
+        assert!(!packings.is_empty());
+        for packing in &packings {
+            assert_eq!("NES", packing[0].orientations());
+            let mut cells: Vec<Position> = packing
+                .iter()
+                .flat_map(|solution| solution.cells())
+                .collect();
+            cells.sort_by_key(|pos| (pos.x, pos.y, pos.z));
+            cells.dedup();
+            assert_eq!(8, cells.len());
+        }
+        // the second square lies on top, starting in any of its corners
+        // in either direction
+        assert_eq!(8, packings.len());
+
+        let output = packing_output(&parser.path.area, &packings[0]);
+        assert!(output.starts_with("chain A start 0 0 0 path NES\nchain B start "));
+        assert!(output.contains("layer z = 0\n A2 A3\n A1 A4\n"));
+        assert_eq!(
+            1,
+            pack(
+                &parser.path.area,
+                &parser.snakes,
+                &parser.path.constraints,
+                1
+            )
+            .len()
+        );
+
+        // the fix line holds for the first snake only
+        let parser = Parser::new(
+            "area cube 2
+chain 4 STTS
+fix 1 0 0 1
+chain 4 STTS
+",
+        );
+        let packings = pack(
+            &parser.path.area,
+            &parser.snakes,
+            &parser.path.constraints,
+            0,
+        );
+        assert!(!packings.is_empty());
+        assert!(packings
+            .iter()
+            .all(|packing| packing[0].bricks[0].coordinates == Position::new(0, 0, 1)));
+
+        // too long together
+        let parser = Parser::new(
+            "area cube 2
+chain 4 STTS
+chain 5 STTTS
+",
+        );
+        assert!(pack(
+            &parser.path.area,
+            &parser.snakes,
+            &parser.path.constraints,
+            0
+        )
+        .is_empty());
+    }
+}
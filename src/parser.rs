@@ -1,79 +1,170 @@
-use crate::area::{Area, Position};
-use crate::path::Path;
-use crate::snake::brick::{Brick, Orientation};
+use crate::area::{Area, Coord, Position};
+use crate::lattice::{self, Lattice};
+use crate::packing::Snake;
+use crate::path::{Constraint, Path};
+use crate::snake::brick::Orientation;
 use crate::snake::chain::{Chain, Form};
+use crate::solution::Solution;
+use crate::verify::{self, Violation};
+use std::rc::Rc;
 use std::result::Result::{Err, Ok};
 use std::vec::Vec;
 
-fn in_cube3(pos: Position) -> bool {
-    let dim = 3;
-    pos.x >= 0 && pos.y >= 0 && pos.z >= 0 && pos.x < dim && pos.y < dim && pos.z < dim
+fn position(lv: &[&str]) -> Position {
+    Position::new(
+        lv[0].parse().expect("parse error"),
+        lv[1].parse().expect("parse error"),
+        lv[2].parse().expect("parse error"),
+    )
 }
 
-fn in_cube4(pos: Position) -> bool {
-    let dim = 4;
-    pos.x >= 0 && pos.y >= 0 && pos.z >= 0 && pos.x < dim && pos.y < dim && pos.z < dim
+// snake the start options refer to, the first one before any chain line
+fn current(snakes: &mut Vec<Snake>) -> &mut Snake {
+    if snakes.is_empty() {
+        snakes.push(Snake::new(Chain::new()));
+    }
+    snakes.last_mut().unwrap()
 }
 
 pub struct Parser {
     inputstring: String,
     pub path: Path,
+    pub start: Position,
+    // up of the first brick, for chains with handed bricks
+    pub up: Option<Orientation>,
+    pub orientations: Vec<Orientation>,
+    pub solutions: Vec<Vec<Orientation>>,
+    // all chains of the input with their start options, the first is
+    // the one of path
+    pub snakes: Vec<Snake>,
 }
 
 impl Parser {
     pub fn new(input: &str) -> Parser {
         let mut area = Area::new();
-        let mut chain = Chain::new();
-        let mut orientations: Vec<Orientation> = Vec::new();
-        let mut startbrick = Position::new(0, 0, 0);
+        let mut snakes: Vec<Snake> = Vec::new();
+        let mut solutions: Vec<Vec<Orientation>> = Vec::new();
+        let mut lattice: Option<Rc<dyn Lattice>> = None;
+        let mut constraints: Vec<Constraint> = Vec::new();
+        let mut end: Option<Position> = None;
+        let mut checker: Option<(char, char)> = None;
+        // lines changing the area, applied once all area lines are read
+        let mut edits: Vec<Vec<&str>> = Vec::new();
 
         for line in input.lines() {
-            let lv: Vec<&str> = line.trim_start().split_whitespace().collect();
+            let lv: Vec<&str> = line.split_whitespace().collect();
+            if lv.is_empty() {
+                continue;
+            }
 
             match lv[0] {
                 "area" => {
-                    // build area
-                    // FIXME: only accept cube for now
-                    if lv[1] != "cube" {
-                        continue;
-                    }
-                    // FIXME: check dim
-                    match lv[2].parse().expect("parse error") {
-                        3 => area.conditions.push(in_cube3),
-                        4 => area.conditions.push(in_cube4),
+                    // build area, several area lines add up
+                    let part = match lv[1] {
+                        "cube" => {
+                            let dim = lv[2].parse().expect("parse error");
+                            Area::cuboid(dim, dim, dim)
+                        }
+                        "box" => Area::cuboid(
+                            lv[2].parse().expect("parse error"),
+                            lv[3].parse().expect("parse error"),
+                            lv[4].parse().expect("parse error"),
+                        ),
+                        "rect" => Area::rect(
+                            lv[2].parse().expect("parse error"),
+                            lv[3].parse().expect("parse error"),
+                        ),
+                        "bitmap" => Area::bitmap(&lv[2..]),
                         _ => continue,
-                    }
+                    };
+                    area = match area.bounds {
+                        None => part,
+                        Some(_) => area.union(&part),
+                    };
+                }
+                "exclude" | "block" | "clip" | "paint" => {
+                    edits.push(lv);
                 }
                 "chain" => {
-                    // build chain
+                    // build chain, every chain line starts a new snake and the
+                    // lines up to the next one refer to it
+                    let mut chain = Chain::new();
                     for ff in lv[2].chars() {
-                        match ff {
-                            'S' => chain.add(Form::Straight),
-                            'T' => chain.add(Form::Turn),
-                            _ => {}
+                        if let Some(frm) = Form::from_char(ff) {
+                            chain.add(frm);
+                        }
+                    }
+                    chain.cyclic = lv.get(3) == Some(&"cyclic");
+                    match snakes.last_mut() {
+                        Some(snake) if snake.chain.len() == 0 => {
+                            snake.chain.dirs = chain.dirs;
+                            snake.chain.cyclic = chain.cyclic;
                         }
+                        _ => snakes.push(Snake::new(chain)),
                     }
                 }
                 "path" => {
                     // build paths
                     for ff in lv[1].chars() {
-                        match ff {
-                            'N' => orientations.push(Orientation::North),
-                            'S' => orientations.push(Orientation::South),
-                            'E' => orientations.push(Orientation::East),
-                            'W' => orientations.push(Orientation::West),
-                            'U' => orientations.push(Orientation::Up),
-                            'D' => orientations.push(Orientation::Down),
-                            _ => {}
+                        if let Some(ori) = Orientation::from_char(ff) {
+                            current(&mut snakes).orientations.push(ori);
                         }
                     }
                 }
+                "lattice" => {
+                    lattice = Some(lattice::from_name(lv[1]).expect("unknown lattice"));
+                }
+                "colours" => {
+                    // one letter per brick, '.' for plain ones
+                    current(&mut snakes).chain.colours = lv[1]
+                        .chars()
+                        .map(|cc| if cc == '.' { None } else { Some(cc) })
+                        .collect();
+                }
+                "checker" => {
+                    let mut colours = lv[1..3].iter().filter_map(|cc| cc.chars().next());
+                    checker = Some((
+                        colours.next().expect("parse error"),
+                        colours.next().expect("parse error"),
+                    ));
+                }
+                "end" => {
+                    end = Some(position(&lv[1..4]));
+                }
+                "fix" => {
+                    // bricks are numbered from 1, as in the renderings
+                    let index: usize = lv[1]
+                        .parse()
+                        .ok()
+                        .filter(|index| *index > 0)
+                        .expect("parse error");
+                    constraints.push(Constraint {
+                        index: index - 1,
+                        position: position(&lv[2..5]),
+                        orientation: lv
+                            .get(5)
+                            .and_then(|ori| ori.chars().next())
+                            .and_then(Orientation::from_char),
+                    });
+                }
+                "up" => {
+                    current(&mut snakes).up = Some(
+                        lv[1]
+                            .chars()
+                            .next()
+                            .and_then(Orientation::from_char)
+                            .expect("parse error"),
+                    );
+                }
+                "solution" => {
+                    solutions.push(lv[1].chars().filter_map(Orientation::from_char).collect());
+                }
                 "start" => {
-                    startbrick = Position {
+                    current(&mut snakes).start = Some(Position {
                         x: lv[1].parse().expect("parser error"),
                         y: lv[2].parse().expect("parser error"),
                         z: lv[3].parse().expect("parser error"),
-                    };
+                    });
                 }
                 _ => {
                     // ignore for now
@@ -81,17 +172,47 @@ impl Parser {
             }
         }
 
+        for lv in &edits {
+            match lv[0] {
+                "exclude" => {
+                    area.excluded.insert(position(&lv[1..4]));
+                }
+                "block" => {
+                    area = area.difference(&Area::span(position(&lv[1..4]), position(&lv[4..7])));
+                }
+                "clip" => {
+                    area = area.intersection(&Area::span(position(&lv[1..4]), position(&lv[4..7])));
+                }
+                _ => {
+                    let colour = lv[4].chars().next().expect("parse error");
+                    area.colours.insert(position(&lv[1..4]), colour);
+                }
+            }
+        }
+
+        // the first snake is the one to fold on its own
+        if snakes.is_empty() {
+            snakes.push(Snake::new(Chain::new()));
+        }
+        let chain = snakes[0].chain.copy();
+        let orientations = snakes[0].orientations.to_vec();
+        let up = snakes[0].up;
+        let mut startbrick = snakes[0].start.unwrap_or(Position::new(0, 0, 0));
+
         for line in input.lines() {
             // FIXME
-            let lv: Vec<&str> = line.trim_start().split_whitespace().collect();
-            match lv[0].parse::<i8>() {
+            let lv: Vec<&str> = line.split_whitespace().collect();
+            if lv.is_empty() {
+                continue;
+            }
+            match lv[0].parse::<Coord>() {
                 Ok(_) => {}
                 Err(_) => continue,
             }
 
             // check for numeric value
             // FIXME: read into array?
-            let coord: Vec<i8> = line
+            let coord: Vec<Coord> = line
                 .split_whitespace()
                 .map(|s| s.parse().expect("parse error"))
                 .collect();
@@ -101,69 +222,115 @@ impl Parser {
             startbrick = Position::new(coord[0], coord[1], coord[2]);
         }
 
-        // keep copy of chain to be able to iterate on forms later
-        // ... probably  not the best way XXX
-        let chain2 = chain.copy();
+        if let Some(lattice) = lattice {
+            area.lattice = lattice;
+        }
+        if let Some((even, odd)) = checker {
+            area.checker(even, odd);
+        }
+
+        if let Some(end) = end {
+            if chain.len() > 0 {
+                constraints.push(Constraint {
+                    index: chain.len() - 1,
+                    position: end,
+                    orientation: None,
+                });
+            }
+        }
+        // the up of a frame has to be perpendicular to its orientation
+        for snake in &snakes {
+            if let (Some(up), Some(ori)) = (snake.up, snake.orientations.first()) {
+                if !up.is_perpendicular(*ori) {
+                    panic!("parse error");
+                }
+            }
+        }
+        // a fix line past the end of the chain could never hold
+        if constraints.iter().any(|cons| cons.index >= chain.len()) {
+            panic!("parse error");
+        }
         let mut path = Path::new(area, chain);
+        path.constraints = constraints;
 
         // build first bricks
-        let mut ot = orientations.iter();
-        // first must be straight -> check for it XXX!
-        //let _fo = ot.next();
-
-        let mut newbrick: Option<Brick> = None;
-
-        for frm in chain2.dirs.iter() {
-            newbrick = match newbrick {
-                None => Some(Brick::new(
-                    startbrick,
-                    *ot.next().unwrap(),
-                    path.chain.get(0).unwrap(),
-                )),
-                Some(nb) => {
-                    match frm {
-                        Form::Straight => Some(nb.next_straight()),
-                        Form::Turn => {
-                            // XXX
-                            match ot.next() {
-                                None => {
-                                    break;
-                                }
-                                Some(ori) => Some(nb.next_turn_orientation(ori)),
-                            }
-                        }
-                    }
-                }
-            };
-            let brk = match newbrick {
-                None => break,
-                Some(brk) => brk,
-            };
+        for brk in snakes[0].prefix(startbrick, &orientations) {
             path.add_brick(&brk);
         }
         Parser {
             path,
             inputstring: input.to_string(),
+            start: startbrick,
+            up,
+            orientations,
+            solutions,
+            snakes,
         }
     }
 
     pub fn output(&self) -> String {
         let mut outputstring = self.inputstring.to_string();
-        outputstring.push_str(&"=========================\n".to_string());
-        if self.path.last_layer.len() < 10 {
+        outputstring.push_str("=========================\n");
+        if self.path.layer_size() < 10 {
             outputstring.push_str(&self.path.solution_string_long());
-            outputstring.push_str(&"-------------------------\n".to_string());
+            outputstring.push_str("-------------------------\n");
             outputstring.push_str(&self.path.solution_string_short());
-            outputstring.push_str(&"-------------------------\n".to_string());
+            outputstring.push_str("-------------------------\n");
         }
         outputstring.push_str(&self.path.solution_string_statistics());
         outputstring
     }
+
+    // the solutions read from the input, each replayed and checked
+    pub fn stored_solutions(&self) -> Vec<Result<Solution, Violation>> {
+        self.solutions
+            .iter()
+            .map(|orientations| {
+                let bricks = verify::replay(&self.path.chain, self.start, self.up, orientations)?;
+                verify::check(
+                    &self.path.area,
+                    &self.path.chain,
+                    &self.path.constraints,
+                    &bricks,
+                )?;
+                Ok(Solution::new(bricks))
+            })
+            .collect()
+    }
+
+    /*
+     * the puzzle as read plus one line per solution in orientation
+     * switch notation, can be read again by the parser; if distinct,
+     * one of every set of solutions equal up to symmetry
+     */
+    pub fn solution_output(&self, distinct: bool) -> String {
+        let mut outputstring = String::new();
+        for line in self.inputstring.lines() {
+            if line.split_whitespace().next() == Some("solution") {
+                continue;
+            }
+            outputstring.push_str(line);
+            outputstring.push('\n');
+        }
+        if self.path.is_complete() {
+            for solution in self.path.solutions(distinct) {
+                outputstring.push_str(&format!("solution {}\n", solution.orientations()));
+            }
+        } else {
+            // not folded (yet), keep the solutions read from the input
+            // which are valid
+            for solution in self.stored_solutions().into_iter().flatten() {
+                outputstring.push_str(&format!("solution {}\n", solution.orientations()));
+            }
+        }
+        outputstring
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::solution::Solution;
 
     #[test]
     fn test_parser() {
@@ -180,4 +347,251 @@ start 0 0 0
         println!("XXXXXXXXXXXXXXXXXXXXXXXXXXXXX");
         println!("{}", parser.output());
     }
+
+    #[test]
+    fn test_solution_roundtrip() {
+        let input = "area cube 3
+chain 27 SSTTTSTTSTTTSTSTTTTSTSTSTSS
+path NE
+start 0 0 0
+";
+        let mut parser = Parser::new(input);
+        parser.path.fold(false);
+        let output = parser.solution_output(false);
+        assert!(output.starts_with(input));
+        assert!(output.ends_with("solution NESUEDNUWSEDEUWNE\n"));
+
+        let reread = Parser::new(&output);
+        assert_eq!(1, reread.solutions.len());
+        assert_eq!(
+            "NESUEDNUWSEDEUWNE",
+            reread.solutions[0]
+                .iter()
+                .map(|ori| ori.to_char())
+                .collect::<String>()
+        );
+        // writing again keeps the solutions without duplicating them
+        assert_eq!(output, reread.solution_output(false));
+
+        // but drops those which are no foldings of the puzzle
+        let reread = Parser::new(&format!("{}solution NWSUEDNUWSEDEUWNE\n", output));
+        let stored = reread.stored_solutions();
+        assert_eq!(2, stored.len());
+        assert!(stored[0].is_ok());
+        assert_eq!(
+            Some(&Violation::OutOfArea(3, Position::new(2, -1, 0))),
+            stored[1].as_ref().err()
+        );
+        assert_eq!(output, reread.solution_output(false));
+    }
+
+    #[test]
+    fn test_planar() {
+        // boustrophedon through a 3x3 square
+        let mut parser = Parser::new(
+            "area rect 3 3
+chain 9 SSTTSTTSS
+path E
+start 0 0 0
+",
+        );
+        assert_eq!(1, parser.path.fold(false));
+        assert!(parser.solution_output(false).ends_with("solution ENWNE\n"));
+
+        // spiral into the centre
+        let mut parser = Parser::new(
+            "area rect 3 3
+chain 9 SSTSTSTTS
+path E
+start 0 0 0
+",
+        );
+        assert_eq!(1, parser.path.fold(false));
+
+        // ring around a hole
+        let mut parser = Parser::new(
+            "area bitmap ### #.# ###
+chain 8 SSTSTSTS
+path E
+start 0 0 0
+",
+        );
+        assert_eq!(1, parser.path.fold(false));
+        let bricks = parser.path.solutions(false).next().unwrap().bricks;
+        assert!(bricks.iter().all(|brk| brk.coordinates.z == 0));
+        assert!(bricks
+            .iter()
+            .all(|brk| brk.coordinates != Position::new(1, 1, 0)));
+    }
+
+    #[test]
+    fn test_obstacles() {
+        let mut parser = Parser::new(
+            "area cube 2
+exclude 1 1 1
+chain 7 STTTTTS
+path N
+start 0 0 0
+",
+        );
+        assert!(parser.path.fold(false) > 0);
+        for solution in parser.path.solutions(false) {
+            assert!(!solution.cells().contains(&Position::new(1, 1, 1)));
+        }
+
+        // too long for the free cells
+        let mut parser = Parser::new(
+            "area cube 2
+exclude 1 1 1
+chain 8 STTTTTTS
+path N
+start 0 0 0
+",
+        );
+        assert_eq!(0, parser.path.fold(false));
+        assert_eq!(1, parser.path.statistics.len());
+
+        // the ring of the bitmap test, cut out of a box
+        let mut parser = Parser::new(
+            "area box 3 3 1
+block 1 1 0 1 1 0
+chain 8 SSTSTSTS
+path E
+start 0 0 0
+",
+        );
+        assert_eq!(1, parser.path.fold(false));
+
+        // two boxes joined by a bar, clipped to a single layer
+        let parser = Parser::new(
+            "area box 2 2 2
+area box 2 5 1
+clip 0 0 0 9 9 0
+",
+        );
+        assert_eq!(Some(10), parser.path.area.volume());
+        assert!(!parser.path.area.is_in(Position::new(0, 0, 1)));
+
+        // obstacles given before the area
+        let parser = Parser::new(
+            "exclude 1 1 1
+block 0 0 0 0 0 0
+area cube 2
+",
+        );
+        assert_eq!(Some(6), parser.path.area.volume());
+    }
+
+    #[test]
+    fn test_constraints() {
+        let puzzle = "area cube 2
+chain 8 STTTTTTS
+path N
+start 0 0 0
+";
+        let mut free = Parser::new(puzzle);
+        assert_eq!(6, free.path.fold(false));
+
+        let mut parser = Parser::new(&format!("{}end 0 0 1\n", puzzle));
+        assert_eq!(2, parser.path.fold(false));
+        for solution in parser.path.solutions(false) {
+            assert_eq!(Position::new(0, 0, 1), solution.bricks[7].coordinates);
+        }
+        // rejected on the way, not at the end
+        let nodes = |path: &Path| path.statistics.iter().map(|(_, nn)| nn).sum::<usize>();
+        assert!(nodes(&parser.path) < nodes(&free.path));
+
+        // wrong parity, nothing gets past the second brick
+        let mut parser = Parser::new(&format!("{}end 1 1 0\n", puzzle));
+        assert_eq!(0, parser.path.fold(false));
+        assert_eq!(2, parser.path.statistics.len());
+
+        let mut parser = Parser::new(&format!("{}fix 4 1 1 1\n", puzzle));
+        assert_eq!(2, parser.path.fold(false));
+        let solutions: Vec<Solution> = parser.path.solutions(false).collect();
+        assert!(solutions
+            .iter()
+            .all(|solution| solution.bricks[3].coordinates == Position::new(1, 1, 1)));
+        let ori = solutions[0].bricks[3].orientation;
+
+        let mut parser = Parser::new(&format!("{}fix 4 1 1 1 {}\n", puzzle, ori.to_char()));
+        assert_eq!(1, parser.path.fold(false));
+        assert_eq!(
+            ori,
+            parser.path.solutions(false).next().unwrap().bricks[3].orientation
+        );
+        assert_eq!(
+            Some(Orientation::North),
+            Parser::new(&format!("{}fix 2 1 0 0 N\n", puzzle))
+                .path
+                .constraints[0]
+                .orientation
+        );
+
+        // the second brick takes the cell of the fourth one
+        let mut parser = Parser::new(&format!("{}fix 4 1 0 0\n", puzzle));
+        assert_eq!(0, parser.path.fold(false));
+        assert_eq!(2, parser.path.statistics.len());
+
+        // the first brick comes from the path line
+        let mut parser = Parser::new(&format!("{}fix 1 1 1 1\n", puzzle));
+        assert_eq!(0, parser.path.fold(false));
+        assert!(!parser.path.is_complete());
+        let mut parser = Parser::new(&format!("{}fix 1 0 0 0 N\n", puzzle));
+        assert_eq!(6, parser.path.fold(false));
+    }
+
+    #[test]
+    #[should_panic(expected = "parse error")]
+    fn test_up_along_path() {
+        Parser::new(
+            "area box 2 2 1
+chain 4 SLLS
+path N
+up S
+start 0 0 0
+",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "parse error")]
+    fn test_fix_past_end() {
+        Parser::new(
+            "area cube 2
+chain 8 STTTTTTS
+fix 9 0 0 0
+",
+        );
+    }
+
+    #[test]
+    fn test_colours() {
+        let puzzle = "area cube 2
+chain 8 STTTTTTS
+path N
+start 0 0 0
+checker w k
+";
+        // every folding alternates between the two colour classes
+        let mut parser = Parser::new(&format!("{}colours wkwkwkwk\n", puzzle));
+        assert_eq!(6, parser.path.fold(false));
+        let mut parser = Parser::new(&format!("{}colours kwkwkwkw\n", puzzle));
+        assert_eq!(0, parser.path.fold(false));
+        assert_eq!(2, parser.path.statistics.len());
+
+        // a painted cell wins over the checkerboard
+        let mut parser = Parser::new(&format!("{}colours ...r\npaint 1 1 1 r\n", puzzle));
+        assert_eq!(Some('r'), parser.path.area.colour(Position::new(1, 1, 1)));
+        assert_eq!(Some('w'), parser.path.area.colour(Position::new(1, 1, 0)));
+        assert_eq!(2, parser.path.fold(false));
+        for solution in parser.path.solutions(false) {
+            assert_eq!(Position::new(1, 1, 1), solution.bricks[3].coordinates);
+        }
+
+        // the first brick comes from the path line and is checked as well
+        let mut parser = Parser::new(&format!("{}colours r\npaint 0 0 0 b\n", puzzle));
+        assert_eq!(0, parser.path.fold(false));
+        assert!(!parser.path.is_complete());
+    }
 }
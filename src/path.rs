@@ -1,62 +1,197 @@
+use crate::area::Coord;
 pub use crate::area::{Area, Position};
-pub use crate::snake::brick::{Brick, Orientation};
+use crate::grid::Grid;
+pub use crate::snake::brick::{Brick, Orientation, Packed};
 pub use crate::snake::chain::{Chain, Form};
-use crate::tree::{Node, TreeIterator};
-use std::mem::swap;
-pub use std::rc::Rc;
+use crate::solution::Solution;
+use crate::symmetry::Symmetry;
+use crate::tree::Tree;
+use std::collections::{HashMap, HashSet};
+use std::mem::size_of;
 use std::vec::Vec;
 
+// brick `index` (counted from 0) has to be at `position`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Constraint {
+    pub index: usize,
+    pub position: Position,
+    pub orientation: Option<Orientation>,
+}
+
 pub struct Path {
     pub area: Area,
     pub chain: Chain,
+    pub constraints: Vec<Constraint>,
+    // the chain fills the whole area, it must not be split then
+    exact_fill: bool,
+    // brick the path starts with, closes a cyclic chain
+    first: Option<Brick>,
+    // ids of the cells, for areas with bounds
+    grid: Option<Grid>,
+    // cells off the grid met by the path, their ids follow those of the grid
+    off_grid: Vec<Position>,
+    off_grid_ids: HashMap<Position, usize>,
 
-    pub last_layer: Vec<Rc<Node<Brick>>>,
+    // all partial paths, the last layer holds the nodes of the longest
+    tree: Tree<Packed>,
+    pub last_layer: Vec<usize>,
+    // size of the tree after the last garbage collection
+    collected: usize,
     last_layer_index: usize,
 
     pub statistics: Vec<(usize, usize)>,
+    // per step: bytes taken by the tree of partial paths and the last layer
+    pub memory: Vec<(usize, usize)>,
+    // per step: number of partial paths of the previous layer without any valid successor
+    pub dead_ends: Vec<(usize, usize)>,
 }
 
 impl Path {
     pub fn new(area: Area, chain: Chain) -> Path {
+        /*
+         * the bricks are packed with the ids of their cells from the start;
+         * grids too large for the ids leave room for cells off the grid and
+         * intern their cells like an area without bounds
+         */
+        let grid = Grid::new(&area, Packed::CELLS / 2);
         Path {
             area,
             chain,
+            constraints: Vec::new(),
+            exact_fill: true,
+            first: None,
+            grid,
+            off_grid: Vec::new(),
+            off_grid_ids: HashMap::new(),
+            tree: Tree::new(),
             last_layer: Vec::new(),
+            collected: 0,
             last_layer_index: 0,
             statistics: Vec::new(),
+            memory: Vec::new(),
+            dead_ends: Vec::new(),
         }
     }
 
     pub fn add_brick(&mut self, brick: &Brick) {
+        // a prefix which broke a colour or a constraint stays dead
+        if self.last_layer.is_empty() && self.last_layer_index > 0 {
+            return;
+        }
         if self.last_layer.is_empty() {
-            self.last_layer.push(Rc::new(Node::<Brick> {
-                father: None,
-                value: *brick,
-            }));
-        } else {
-            self.last_layer[0] = Rc::new(Node::<Brick> {
-                father: Some(Rc::clone(&self.last_layer[0])),
-                value: *brick,
-            });
-        }
-
-        self.statistics.push((self.last_layer_index, 1));
+            self.first = Some(*brick);
+        }
+        if !self.matches_colour(brick) || !self.keeps_constraints(brick) {
+            self.last_layer.clear();
+            self.record(self.last_layer_index, 0);
+            self.last_layer_index += 1;
+            return;
+        }
+        let packed = self
+            .pack(brick)
+            .expect("too many cells to pack the first bricks");
+        let node = self.tree.add(self.last_layer.first().copied(), packed);
+        self.last_layer = vec![node];
+
+        self.record(self.last_layer_index, 1);
         self.last_layer_index += 1;
     }
 
+    fn record(&mut self, step: usize, paths: usize) {
+        self.statistics.push((step, paths));
+        let bytes = self.tree.bytes() + self.last_layer.capacity() * size_of::<usize>();
+        self.memory.push((step, bytes));
+    }
+
+    // id of a cell, None for cells off the grid the path never met
+    fn cell(&self, pos: Position) -> Option<usize> {
+        match self.grid.as_ref().and_then(|grid| grid.id(pos)) {
+            Some(id) => Some(id),
+            None => self.off_grid_ids.get(&pos).copied(),
+        }
+    }
+
+    // None once all ids are taken
+    fn pack(&mut self, brk: &Brick) -> Option<Packed> {
+        let pos = brk.coordinates;
+        let id = match self.cell(pos) {
+            Some(id) => id,
+            None => {
+                let id = self.grid.as_ref().map_or(0, |grid| grid.len()) + self.off_grid.len();
+                if id >= Packed::CELLS {
+                    return None;
+                }
+                self.off_grid.push(pos);
+                self.off_grid_ids.insert(pos, id);
+                id
+            }
+        };
+        Some(brk.pack(id))
+    }
+
+    // drops the dead partial paths and the cells off the grid only they met
+    fn collect(&mut self) {
+        self.last_layer = self.tree.collect(&self.last_layer);
+        self.collected = self.tree.len();
+
+        let base = self.grid.as_ref().map_or(0, |grid| grid.len());
+        let old = std::mem::take(&mut self.off_grid);
+        self.off_grid_ids.clear();
+        for packed in self.tree.values_mut() {
+            if packed.cell() < base {
+                continue;
+            }
+            let pos = old[packed.cell() - base];
+            let id = match self.off_grid_ids.get(&pos) {
+                Some(id) => *id,
+                None => {
+                    self.off_grid.push(pos);
+                    self.off_grid_ids
+                        .insert(pos, base + self.off_grid.len() - 1);
+                    base + self.off_grid.len() - 1
+                }
+            };
+            *packed = packed.unpack(pos).pack(id);
+        }
+    }
+
+    fn unpack(&self, packed: Packed) -> Brick {
+        let id = packed.cell();
+        let pos = match &self.grid {
+            Some(grid) if id < grid.len() => grid.position(id),
+            _ => self.off_grid[id - self.grid.as_ref().map_or(0, |grid| grid.len())],
+        };
+        packed.unpack(pos)
+    }
+
+    // bricks of a partial path from its end to the start
+    fn bricks(&self, node: usize) -> impl Iterator<Item = Brick> + '_ {
+        self.tree.iter(node).map(move |packed| self.unpack(packed))
+    }
+
     pub fn fold(&mut self, verbose: bool) -> usize {
+        // the chain does not fit into the free cells at all
+        match &self.grid {
+            Some(grid) => {
+                if self.chain.len() > grid.len() {
+                    return 0;
+                }
+                self.exact_fill = self.chain.len() == grid.len();
+            }
+            None => self.exact_fill = self.area.volume() == Some(self.chain.len()),
+        }
         let mut lsize = 0;
         for ii in self.last_layer_index..self.chain.len() {
             match self.chain.get(ii) {
                 Some(frm) => {
                     lsize = self.build_next_layer(frm);
-                    self.statistics.push((ii, lsize));
+                    self.record(ii, lsize);
                     if lsize == 0 {
                         break;
                     }
                 }
                 None => {
-                    self.statistics.push((ii, 0));
+                    self.record(ii, 0);
                     break;
                 }
             }
@@ -71,27 +206,25 @@ impl Path {
         lsize
     }
 
-    fn self_intersect(coords: Position, node: Rc<Node<Brick>>) -> bool {
-        for rr in TreeIterator::new(Rc::clone(&node)) {
-            if rr.value.coordinates == coords {
-                return true;
-            }
+    fn self_intersect(&self, coords: Position, node: usize) -> bool {
+        match self.cell(coords) {
+            None => false,
+            Some(id) => self.tree.iter(node).any(|packed| packed.cell() == id),
         }
-        false
     }
 
     #[inline]
-    fn valid(path: &Path, coords: Position, nr: &Rc<Node<Brick>>) -> bool {
-        path.area.is_in(coords) && !Path::self_intersect(coords, Rc::clone(nr))
+    fn valid(path: &Path, coords: Position, nr: usize) -> bool {
+        path.area.is_in(coords) && !path.self_intersect(coords, nr)
     }
 
     /*
      * checks if area is splitted by new position
      */
-    fn split_area(&self, coords: Position, node: &Rc<Node<Brick>>) -> bool {
+    fn split_area(&self, coords: Position, node: usize) -> bool {
         // pick first valid neighbour
         let mut fnb: Option<Position> = None;
-        for nb in &coords.neighbours() {
+        for nb in &self.area.lattice.neighbours(coords) {
             if Path::valid(self, *nb, node) {
                 fnb = Some(*nb);
                 break;
@@ -108,13 +241,9 @@ impl Path {
         self.chain.dirs.len() - self.last_layer_index > complement.len()
     }
 
-    fn build_complement(
-        complement: &mut Vec<Position>,
-        pos: Position,
-        path: &Path,
-        node: &Rc<Node<Brick>>,
-    ) {
-        if complement.len() > 100 {
+    // stops once there is room for the rest of the chain
+    fn build_complement(complement: &mut Vec<Position>, pos: Position, path: &Path, node: usize) {
+        if complement.len() >= path.chain.dirs.len() - path.last_layer_index {
             return;
         }
         // if pos in complement-> return
@@ -125,71 +254,231 @@ impl Path {
         }
         if Path::valid(path, pos, node) {
             complement.push(pos);
-            for nb in &pos.neighbours() {
+            for nb in &path.area.lattice.neighbours(pos) {
                 Path::build_complement(complement, *nb, path, node);
             }
         }
     }
 
-    fn valid_nosplit(&self, coords: Position, nr: &Rc<Node<Brick>>) -> bool {
+    // the colour of the brick of the next layer, if both are painted
+    fn matches_colour(&self, brk: &Brick) -> bool {
+        match (
+            self.chain.colour(self.last_layer_index),
+            self.area.colour(brk.coordinates),
+        ) {
+            (Some(colour), Some(target)) => colour == target,
+            _ => true,
+        }
+    }
+
+    // the fix and end lines, for the brick of the next layer
+    fn keeps_constraints(&self, brk: &Brick) -> bool {
+        let index = self.last_layer_index;
+        for cons in &self.constraints {
+            if cons.index < index {
+                continue;
+            }
+            if cons.index == index {
+                if cons.position != brk.coordinates
+                    || cons.orientation.is_some_and(|ori| ori != brk.orientation)
+                {
+                    return false;
+                }
+                continue;
+            }
+            if brk.coordinates == cons.position
+                || !self
+                    .area
+                    .lattice
+                    .reachable(brk.coordinates, cons.position, cons.index - index)
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /*
+     * the new brick of the next layer matches the target colour of its
+     * cell, keeps all constraints reachable (no other brick may take
+     * their cells) and can still close a cyclic chain: the last brick has
+     * to point to the first one, with the joint of the first brick
+     */
+    fn satisfies(&self, brk: &Brick) -> bool {
+        let index = self.last_layer_index;
+        if !self.matches_colour(brk) || !self.keeps_constraints(brk) {
+            return false;
+        }
+        if let (true, Some(first)) = (self.chain.cyclic, self.first) {
+            let len = self.chain.len();
+            if !self
+                .area
+                .lattice
+                .reachable(brk.coordinates, first.coordinates, len - index)
+            {
+                return false;
+            }
+            if index + 1 == len
+                && (self.area.lattice.step(brk.coordinates, brk.orientation)
+                    != Some(first.coordinates)
+                    || !self.area.lattice.allows(
+                        brk,
+                        self.chain.get(0).unwrap(),
+                        first.orientation,
+                    ))
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn valid_nosplit(&self, coords: Position, nr: usize) -> bool {
         self.area.is_in(coords)
-            && !Path::self_intersect(coords, Rc::clone(nr))
-            && !self.split_area(coords, nr)
+            && !self.self_intersect(coords, nr)
+            && !(self.exact_fill && self.split_area(coords, nr))
+    }
+
+    // marks (or clears) the cells of the bricks of a partial path
+    fn mark(&self, node: usize, taken: &mut [bool], value: bool) {
+        for packed in self.tree.iter(node) {
+            if let Some(cell) = taken.get_mut(packed.cell()) {
+                *cell = value;
+            }
+        }
+    }
+
+    /*
+     * split_area on the cell ids: the free cells connected to the first
+     * free neighbour of the new cell have to take the rest of the chain
+     */
+    fn splits(&self, grid: &Grid, id: usize, taken: &mut [bool]) -> bool {
+        let fnb = match grid.neighbours(id).iter().find(|nb| !taken[**nb]) {
+            None => return false,
+            Some(nb) => *nb,
+        };
+        let remaining = self.chain.dirs.len() - self.last_layer_index;
+        let mut complement = vec![fnb];
+        taken[fnb] = true;
+        let mut next = 0;
+        while next < complement.len() && complement.len() < remaining {
+            for nb in grid.neighbours(complement[next]) {
+                if !taken[*nb] {
+                    taken[*nb] = true;
+                    complement.push(*nb);
+                }
+            }
+            next += 1;
+        }
+        for cc in &complement {
+            taken[*cc] = false;
+        }
+        remaining > complement.len()
+    }
+
+    // taken holds the cells of nr if the area has a grid
+    fn fits(&self, coords: Position, nr: usize, taken: &mut [bool]) -> bool {
+        match &self.grid {
+            None => self.valid_nosplit(coords, nr),
+            Some(grid) => match grid.id(coords) {
+                Some(id) if !taken[id] => !(self.exact_fill && self.splits(grid, id, taken)),
+                _ => false,
+            },
+        }
     }
 
     fn build_next_layer(&mut self, frm: Form) -> usize {
-        let mut new_layer: Vec<Rc<Node<Brick>>> = Vec::new();
+        let mut new_layer: Vec<usize> = Vec::new();
+        let mut children: Vec<Brick> = Vec::new();
+        let mut dead = 0;
+        let mut taken = vec![false; self.grid.as_ref().map_or(0, |grid| grid.len())];
 
         // iterate on last_layer
-        for nr in &self.last_layer {
-            let mut process_brick = |brk: &Brick| {
-                if self.valid_nosplit(brk.coordinates, nr) {
-                    new_layer.push(Rc::new(Node::<Brick> {
-                        father: Some(Rc::clone(nr)),
-                        value: *brk,
-                    }));
+        for ii in 0..self.last_layer.len() {
+            let nr = self.last_layer[ii];
+            let value = self.unpack(self.tree.value(nr));
+            if self.grid.is_some() {
+                self.mark(nr, &mut taken, true);
+            }
+            for brk in self.area.lattice.successors(&value, frm) {
+                if self.satisfies(&brk) && self.fits(brk.coordinates, nr, &mut taken) {
+                    children.push(brk);
                 }
-            };
-            match frm {
-                Form::Straight => process_brick(&(*nr).value.next_straight()),
-                Form::Turn => {
-                    for new_brick in &(*nr).value.next_turn() {
-                        process_brick(new_brick);
+            }
+            if self.grid.is_some() {
+                self.mark(nr, &mut taken, false);
+            }
+            if children.is_empty() {
+                dead += 1;
+            }
+            for brk in children.drain(..) {
+                match self.pack(&brk) {
+                    Some(packed) => new_layer.push(self.tree.add(Some(nr), packed)),
+                    // out of cell ids, give up like for too many paths
+                    None => {
+                        self.dead_ends.push((self.last_layer_index, dead));
+                        return 0;
                     }
                 }
             }
         }
+        self.dead_ends.push((self.last_layer_index, dead));
         if new_layer.is_empty() {
             return 0;
         }
-        swap(&mut self.last_layer, &mut new_layer);
+        self.last_layer = new_layer;
         self.last_layer_index += 1;
 
+        // drop the partial paths that died once the tree has doubled
+        if self.tree.len() > 2 * self.collected {
+            self.collect();
+        }
+
         self.last_layer.len()
     }
 
+    pub fn is_complete(&self) -> bool {
+        !self.last_layer.is_empty() && self.last_layer_index == self.chain.len()
+    }
+
+    // partial paths of the last layer, the solutions once complete
+    pub fn layer_size(&self) -> usize {
+        self.last_layer.len()
+    }
+
+    /*
+     * all complete solutions; if distinct, only the first of those equal
+     * up to symmetries of the area and reversal
+     */
+    pub fn solutions(&self, distinct: bool) -> Solutions<'_> {
+        Solutions {
+            path: self,
+            next: if self.is_complete() {
+                0
+            } else {
+                self.last_layer.len()
+            },
+            symmetries: if distinct {
+                Symmetry::of_area(&self.area, self.chain.is_handed())
+            } else {
+                Vec::new()
+            },
+            known: if distinct { Some(HashSet::new()) } else { None },
+        }
+    }
+
     pub fn solution_string_long(&self) -> String {
         let mut output = "coords   form orientation\n".to_string();
         for nr in &self.last_layer {
-            for rr in TreeIterator::new(Rc::clone(&nr)) {
+            for brk in self.bricks(*nr) {
                 let mut line = format!(
                     "[{}, {}, {}]  ",
-                    rr.value.coordinates.x, rr.value.coordinates.y, rr.value.coordinates.z,
+                    brk.coordinates.x, brk.coordinates.y, brk.coordinates.z,
                 );
-                line.push_str(match rr.value.form {
-                    Form::Straight => "S",
-                    Form::Turn => "T",
-                });
+                line.push(brk.form.to_char());
                 line.push_str("  ");
-                line.push_str(match rr.value.orientation {
-                    Orientation::North => "N",
-                    Orientation::South => "S",
-                    Orientation::East => "E",
-                    Orientation::West => "W",
-                    Orientation::Up => "U",
-                    Orientation::Down => "D",
-                });
-                line.push_str("\n");
+                line.push(brk.orientation.to_char());
+                line.push('\n');
                 output.push_str(&line);
             }
             output.push_str("------------------\n");
@@ -201,19 +490,12 @@ impl Path {
         let mut output = "orientation_switches\n".to_string();
         for nr in &self.last_layer {
             let mut last_orientation: Option<Orientation> = None;
-            for rr in TreeIterator::new(Rc::clone(&nr)) {
-                if last_orientation.is_none() || last_orientation.unwrap() != rr.value.orientation {
-                    output.push_str(match rr.value.orientation {
-                        Orientation::North => "N",
-                        Orientation::South => "S",
-                        Orientation::East => "E",
-                        Orientation::West => "W",
-                        Orientation::Up => "U",
-                        Orientation::Down => "D",
-                    });
-                    output.push_str("\n")
+            for brk in self.bricks(*nr) {
+                if last_orientation.is_none() || last_orientation.unwrap() != brk.orientation {
+                    output.push(brk.orientation.to_char());
+                    output.push('\n')
                 }
-                last_orientation = Some(rr.value.orientation);
+                last_orientation = Some(brk.orientation);
             }
         }
         output
@@ -221,9 +503,9 @@ impl Path {
 
     pub fn solution_string_statistics(&self) -> String {
         let mut output = "statistics\n".to_string();
-        output.push_str("step number_paths\n");
-        for tt in &self.statistics {
-            output.push_str(&format!("{}\t{}\n", tt.0 + 1, tt.1));
+        output.push_str("step number_paths bytes\n");
+        for (tt, (_, bytes)) in self.statistics.iter().zip(&self.memory) {
+            output.push_str(&format!("{}\t{}\t{}\n", tt.0 + 1, tt.1, bytes));
         }
         output
     }
@@ -231,7 +513,7 @@ impl Path {
     #[cfg(test)]
     pub fn print_layer(&self) {
         for nr in &self.last_layer {
-            println!("{:?}", (*nr).value);
+            println!("{:?}", self.unpack(self.tree.value(*nr)));
         }
     }
 
@@ -239,9 +521,43 @@ impl Path {
     pub fn print_solution(&self) {
         for nr in &self.last_layer {
             println!("++++++++++++++++++++++++++++++++++++++++++++");
-            for rr in TreeIterator::new(Rc::clone(&nr)) {
-                println!("{:?}", rr.value);
+            for brk in self.bricks(*nr) {
+                println!("{:?}", brk);
+            }
+        }
+    }
+}
+
+pub struct Solutions<'a> {
+    path: &'a Path,
+    next: usize,
+    symmetries: Vec<Symmetry>,
+    // keys of the solutions so far, if they have to be distinct
+    known: Option<HashSet<Vec<(Coord, Coord, Coord)>>>,
+}
+
+impl<'a> Iterator for Solutions<'a> {
+    type Item = Solution;
+
+    fn next(&mut self) -> Option<Solution> {
+        loop {
+            let nr = *self.path.last_layer.get(self.next)?;
+            self.next += 1;
+            let mut bricks: Vec<Brick> = self.path.bricks(nr).collect();
+            bricks.reverse();
+            let solution = Solution::new(bricks);
+            if let Some(keys) = &mut self.known {
+                let lower = self
+                    .path
+                    .area
+                    .bounds
+                    .map_or(Position::new(0, 0, 0), |bb| bb.0);
+                let key = solution.key(lower, &self.symmetries, self.path.chain.cyclic);
+                if !keys.insert(key) {
+                    continue;
+                }
             }
+            return Some(solution);
         }
     }
 }
@@ -359,4 +675,140 @@ mod tests {
             println!("{}\t{}", tt.0, tt.1)
         }
     }
+
+    #[test]
+    fn test_far_off() {
+        // the cube of test_cubesnake_small, moved far from the origin
+        let corner = Position::new(1000, -1000, 20000);
+        let area = Area::span(
+            corner,
+            Position::new(corner.x + 2, corner.y + 2, corner.z + 2),
+        );
+        let mut chain = Chain::new();
+        for frm in "SSTTTSTTSTTTSTSTTTTSTSTSTSS".chars() {
+            chain.add(Form::from_char(frm).unwrap());
+        }
+        let mut path = Path::new(area, chain);
+        path.add_brick(&Brick::new(corner, Orientation::North, Form::Straight));
+        path.add_brick(&Brick::new(
+            Position::new(corner.x + 1, corner.y, corner.z),
+            Orientation::North,
+            Form::Straight,
+        ));
+        path.add_brick(&Brick::new(
+            Position::new(corner.x + 2, corner.y, corner.z),
+            Orientation::East,
+            Form::Turn,
+        ));
+        assert_eq!(1, path.fold(false));
+
+        // a node takes a packed brick and the index of its father
+        assert_eq!(path.statistics.len(), path.memory.len());
+        let (step, bytes) = path.memory[2];
+        assert_eq!(2, step);
+        assert!(bytes >= 3 * 8 + 8);
+        assert!(path
+            .solution_string_statistics()
+            .starts_with("statistics\nstep number_paths bytes\n1\t1\t"));
+    }
+
+    #[test]
+    fn test_big_grid() {
+        use crate::parser::Parser;
+
+        // more cells than fit into a packed brick
+        let mut parser = Parser::new(
+            "area cube 81
+chain 4 STTS
+path S
+start 80 80 80
+",
+        );
+        assert!(parser.path.grid.is_none());
+        assert_eq!(6, parser.path.fold(false));
+        for solution in parser.path.solutions(false) {
+            assert_eq!(Position::new(80, 80, 80), solution.cells()[0]);
+        }
+
+        // a long chain through such an area is not taken as splitting it
+        let mut parser = Parser::new(&format!(
+            "area box 600 600 1
+chain 152 TT{}
+path N
+start 0 0 0
+",
+            "S".repeat(150)
+        ));
+        assert!(parser.path.grid.is_none());
+        assert_eq!(1, parser.path.fold(false));
+    }
+
+    #[test]
+    fn test_solutions() {
+        use crate::parser::Parser;
+
+        let mut parser = Parser::new(
+            "area cube 2
+chain 8 STTTTTTS
+path N
+start 0 0 0
+",
+        );
+        assert_eq!(0, parser.path.solutions(false).count());
+        assert_eq!(6, parser.path.fold(false));
+        for solution in parser.path.solutions(false) {
+            assert_eq!(8, solution.bricks.len());
+            assert_eq!(Position::new(0, 0, 0), solution.cells()[0]);
+            assert!(solution.orientations().starts_with('N'));
+        }
+        // the three shapes of hamiltonian paths through the cube
+        assert_eq!(3, parser.path.solutions(true).count());
+    }
+
+    #[test]
+    fn test_cyclic() {
+        use crate::parser::Parser;
+
+        let mut parser = Parser::new(
+            "area box 2 2 1
+chain 4 TTTT cyclic
+path N
+start 0 0 0
+",
+        );
+        assert!(parser.path.chain.cyclic);
+        assert_eq!(1, parser.path.fold(false));
+        // the last brick points back to the first
+        let solution = parser.path.solutions(false).next().unwrap();
+        assert_eq!(Orientation::West, solution.bricks[3].orientation);
+
+        // open, the last brick may point anywhere
+        let mut parser = Parser::new(
+            "area box 2 2 1
+chain 4 TTTT
+path N
+start 0 0 0
+",
+        );
+        assert_eq!(4, parser.path.fold(false));
+
+        // the closing joint has to be straight
+        let mut parser = Parser::new(
+            "area box 2 2 1
+chain 4 STTT cyclic
+path N
+start 0 0 0
+",
+        );
+        assert_eq!(0, parser.path.fold(false));
+
+        let mut parser = Parser::new(
+            "area bitmap ### #.# ###
+chain 8 TSTSTSTS cyclic
+path N
+start 0 0 0
+",
+        );
+        assert_eq!(1, parser.path.fold(false));
+    }
 }
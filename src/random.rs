@@ -0,0 +1,68 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// small xorshift64* generator, good enough to shuffle search orders
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        // splitmix64 step, spreads small seeds and avoids the zero state
+        let mut zz = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        zz = (zz ^ (zz >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        zz = (zz ^ (zz >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        zz ^= zz >> 31;
+        Random {
+            state: if zz == 0 { 1 } else { zz },
+        }
+    }
+
+    pub fn from_time() -> Random {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|dd| dd.as_nanos() as u64)
+            .unwrap_or(0);
+        Random::new(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    // uniform in 0..nn
+    pub fn below(&mut self, nn: usize) -> usize {
+        (self.next_u64() % nn as u64) as usize
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for ii in (1..items.len()).rev() {
+            let jj = self.below(ii + 1);
+            items.swap(ii, jj);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random() {
+        let mut aa = Random::new(42);
+        let mut bb = Random::new(42);
+        let mut cc = Random::new(43);
+        let first = aa.next_u64();
+        assert_eq!(first, bb.next_u64());
+        assert_ne!(first, cc.next_u64());
+        for _ in 0..100 {
+            assert!(aa.below(7) < 7);
+        }
+        let mut items = [1, 2, 3, 4, 5];
+        aa.shuffle(&mut items);
+        items.sort();
+        assert_eq!([1, 2, 3, 4, 5], items);
+    }
+}
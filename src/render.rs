@@ -0,0 +1,153 @@
+use crate::area::{Area, Position};
+use crate::snake::brick::Brick;
+use crate::snake::chain::Chain;
+
+/*
+ * colours of bricks and cells are single letters, known ones are drawn
+ * with an ansi code and a hue, saturation and lightness
+ */
+pub fn palette(colour: char) -> Option<(&'static str, (u32, u32, u32))> {
+    match colour {
+        'r' => Some(("31", (0, 75, 50))),
+        'o' => Some(("38;5;208", (30, 90, 55))),
+        'y' => Some(("33", (55, 90, 55))),
+        'g' => Some(("32", (120, 60, 40))),
+        'c' => Some(("36", (185, 70, 50))),
+        'b' => Some(("34", (220, 70, 50))),
+        'p' => Some(("35", (290, 60, 50))),
+        'w' => Some(("97", (0, 0, 92))),
+        'k' => Some(("90", (0, 0, 20))),
+        _ => None,
+    }
+}
+
+// smallest box containing all bricks
+pub fn bounds(bricks: &[Brick]) -> Option<(Position, Position)> {
+    let first = bricks.first()?.coordinates;
+    let mut lower = first;
+    let mut upper = first;
+    for brk in bricks {
+        let pos = brk.coordinates;
+        lower = Position::new(lower.x.min(pos.x), lower.y.min(pos.y), lower.z.min(pos.z));
+        upper = Position::new(upper.x.max(pos.x), upper.y.max(pos.y), upper.z.max(pos.z));
+    }
+    Some((lower, upper))
+}
+
+/*
+ * one grid per z layer, north (x) to the top and east (y) to the right;
+ * every cell shows the brick number along the chain and its orientation
+ * in the colour of the brick, free cells of the area are marked with a
+ * dot or the letter of their target colour
+ */
+pub fn layers(area: &Area, chain: &Chain, bricks: &[Brick]) -> String {
+    grid(area, bricks, bricks.len(), |ii| {
+        chain.colour(ii).and_then(palette).map(|(code, _)| code)
+    })
+}
+
+/*
+ * like layers, but only the first `shown` bricks are placed and every
+ * placed brick can be given an ansi style (e.g. "1;31")
+ */
+pub fn grid<F>(area: &Area, bricks: &[Brick], shown: usize, style: F) -> String
+where
+    F: Fn(usize) -> Option<&'static str>,
+{
+    let (lower, upper) = match bounds(bricks) {
+        None => return String::new(),
+        Some(bb) => bb,
+    };
+    let width = format!("{}", bricks.len()).len() + 2;
+    let placed = &bricks[..shown.min(bricks.len())];
+
+    let mut output = String::new();
+    for z in lower.z..=upper.z {
+        output.push_str(&format!("layer z = {}\n", z));
+        output.push_str(&format!("{:>4}", "x\\y"));
+        for y in lower.y..=upper.y {
+            output.push_str(&format!("{:>w$}", y, w = width));
+        }
+        output.push('\n');
+        for x in (lower.x..=upper.x).rev() {
+            output.push_str(&format!("{:>4}", x));
+            for y in lower.y..=upper.y {
+                let pos = Position::new(x, y, z);
+                let (cell, code) = match placed.iter().position(|brk| brk.coordinates == pos) {
+                    Some(ii) => (
+                        format!("{}{}", ii + 1, placed[ii].orientation.to_char()),
+                        style(ii),
+                    ),
+                    None if area.is_in(pos) => match area.colour(pos) {
+                        Some(colour) => (colour.to_string(), palette(colour).map(|(code, _)| code)),
+                        None => (".".to_string(), None),
+                    },
+                    None => (String::new(), None),
+                };
+                // pad before styling, escape codes have no width
+                let cell = format!("{:>w$}", cell, w = width);
+                match code {
+                    Some(code) => output.push_str(&format!("\x1b[{}m{}\x1b[0m", code, cell)),
+                    None => output.push_str(&cell),
+                }
+            }
+            output.push('\n');
+        }
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::test_support::cube_folding;
+    use crate::verify::{parse_orientations, replay};
+
+    #[test]
+    fn test_layers() {
+        let (parser, mut bricks) = cube_folding();
+        let output = layers(&parser.path.area, &parser.path.chain, &bricks);
+        assert!(output.starts_with(
+            "layer z = 0
+ x\\y   0   1   2
+   2  3E  4S 11U
+   1  2N  5U 10N
+   0  1N 18E 19U
+"
+        ));
+        assert_eq!(3, output.matches("layer").count());
+
+        // cells of the area which are not covered
+        bricks.truncate(4);
+        let output = layers(&parser.path.area, &parser.path.chain, &bricks);
+        assert!(output.contains("   1 2N  .\n"));
+    }
+
+    #[test]
+    fn test_colours() {
+        let parser = Parser::new(
+            "area box 2 2 1
+chain 4 STTS
+colours r.bx
+paint 0 1 0 g
+",
+        );
+        let bricks = replay(
+            &parser.path.chain,
+            parser.start,
+            None,
+            &parse_orientations("NES"),
+        )
+        .unwrap();
+        let output = layers(&parser.path.area, &parser.path.chain, &bricks[..3]);
+        assert!(output.contains("\x1b[31m 1N\x1b[0m"));
+        assert!(output.contains(" 2E"));
+        assert!(output.contains("\x1b[34m 3S\x1b[0m"));
+        // target colour of a free cell
+        assert!(output.contains("\x1b[32m  g\x1b[0m"));
+        // unknown colours are not drawn
+        assert_eq!(None, palette('x'));
+    }
+}
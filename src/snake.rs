@@ -1,27 +1,138 @@
 pub mod chain {
+    use super::brick::{Brick, Orientation};
     use std::vec::Vec;
 
-    #[derive(Copy, Clone, Debug)]
+    /*
+     * the joint at the start of a brick, i.e. the set of orientations the
+     * brick may take relative to the orientation of its predecessor
+     *
+     * the twisted forms refer to the plane of the last turn, spanned by
+     * the incoming orientation and the one before the last turn; as long
+     * as there was no turn they allow every perpendicular orientation
+     *
+     * the handed forms refer to the frame (forward and up) carried along
+     * the chain by the predecessor
+     */
+    #[derive(Copy, Clone, Debug, PartialEq)]
     pub enum Form {
         Straight,
+        // any of the four perpendicular orientations
         Turn,
+        // straight or any turn
+        Free,
+        // turn within the plane of the last turn
+        Planar,
+        // turn out of the plane of the last turn
+        Twisted,
+        // turn back into the orientation before the last turn
+        Zigzag,
+        // turn against the orientation before the last turn
+        Back,
+        // turn to the left or right of the frame, seen from above
+        Left,
+        Right,
+    }
+
+    const FORMS: [Form; 9] = [
+        Form::Straight,
+        Form::Turn,
+        Form::Free,
+        Form::Planar,
+        Form::Twisted,
+        Form::Zigzag,
+        Form::Back,
+        Form::Left,
+        Form::Right,
+    ];
+
+    impl Form {
+        // inverse of `frm as usize`
+        pub fn from_index(index: usize) -> Form {
+            FORMS[index]
+        }
+
+        pub fn from_char(c: char) -> Option<Form> {
+            match c {
+                'S' => Some(Form::Straight),
+                'T' => Some(Form::Turn),
+                'X' => Some(Form::Free),
+                'P' => Some(Form::Planar),
+                'O' => Some(Form::Twisted),
+                'Z' => Some(Form::Zigzag),
+                'U' => Some(Form::Back),
+                'L' => Some(Form::Left),
+                'R' => Some(Form::Right),
+                _ => None,
+            }
+        }
+
+        pub fn to_char(self) -> char {
+            match self {
+                Form::Straight => 'S',
+                Form::Turn => 'T',
+                Form::Free => 'X',
+                Form::Planar => 'P',
+                Form::Twisted => 'O',
+                Form::Zigzag => 'Z',
+                Form::Back => 'U',
+                Form::Left => 'L',
+                Form::Right => 'R',
+            }
+        }
+
+        pub fn is_handed(self) -> bool {
+            matches!(self, Form::Left | Form::Right)
+        }
+
+        // may a brick with this form follow `from` in orientation `outgoing`
+        pub fn allows(self, from: &Brick, outgoing: Orientation) -> bool {
+            let incoming = from.orientation;
+            let turn = incoming.is_perpendicular(outgoing);
+            match (self, from.previous) {
+                (Form::Straight, _) => outgoing == incoming,
+                (Form::Turn, _) => turn,
+                (Form::Free, _) => turn || outgoing == incoming,
+                (Form::Left, _) => Some(outgoing) == from.left(),
+                (Form::Right, _) => Some(outgoing.opposite()) == from.left(),
+                (_, None) => turn,
+                (Form::Planar, Some(prev)) => turn && !outgoing.is_perpendicular(prev),
+                (Form::Twisted, Some(prev)) => turn && outgoing.is_perpendicular(prev),
+                (Form::Zigzag, Some(prev)) => turn && outgoing == prev,
+                (Form::Back, Some(prev)) => turn && outgoing == prev.opposite(),
+            }
+        }
     }
 
     pub struct Chain {
         pub dirs: Vec<Form>,
+        // colour of each brick as a letter, missing at the end or None
+        // for plain bricks
+        pub colours: Vec<Option<char>>,
+        // closed ring, the first brick follows the last one
+        pub cyclic: bool,
     }
 
     impl Chain {
         pub fn new() -> Chain {
-            Chain { dirs: Vec::new() }
+            Chain {
+                dirs: Vec::new(),
+                colours: Vec::new(),
+                cyclic: false,
+            }
         }
 
         pub fn copy(&self) -> Chain {
             Chain {
                 dirs: self.dirs.to_vec(),
+                colours: self.colours.to_vec(),
+                cyclic: self.cyclic,
             }
         }
 
+        pub fn colour(&self, index: usize) -> Option<char> {
+            self.colours.get(index).cloned().unwrap_or(None)
+        }
+
         pub fn add(&mut self, form: Form) {
             self.dirs.push(form);
         }
@@ -30,6 +141,10 @@ pub mod chain {
             self.dirs.len()
         }
 
+        pub fn is_handed(&self) -> bool {
+            self.dirs.iter().any(|frm| frm.is_handed())
+        }
+
         pub fn get(&self, index: usize) -> Option<Form> {
             if index < self.dirs.len() {
                 Some(self.dirs[index])
@@ -42,45 +157,31 @@ pub mod chain {
 
 pub mod brick {
     use super::chain::Form;
-    use std::ops;
+    use std::vec::Vec;
 
-    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    // wide enough for far off positions, steps never leave its range
+    pub type Coord = i16;
+
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
     pub struct Position {
-        pub x: i8,
-        pub y: i8,
-        pub z: i8,
-    }
-
-    impl ops::Add<Orientation> for Position {
-        type Output = Position;
-
-        #[allow(clippy::suspicious_arithmetic_impl)]
-        fn add(self, rhs: Orientation) -> Position {
-            match rhs {
-                Orientation::North => Position::new(self.x + 1, self.y, self.z),
-                Orientation::South => Position::new(self.x - 1, self.y, self.z),
-                Orientation::East => Position::new(self.x, self.y + 1, self.z),
-                Orientation::West => Position::new(self.x, self.y - 1, self.z),
-                Orientation::Up => Position::new(self.x, self.y, self.z + 1),
-                Orientation::Down => Position::new(self.x, self.y, self.z - 1),
-            }
-        }
+        pub x: Coord,
+        pub y: Coord,
+        pub z: Coord,
     }
 
     impl Position {
-        pub fn new(x: i8, y: i8, z: i8) -> Position {
+        pub fn new(x: Coord, y: Coord, z: Coord) -> Position {
             Position { x, y, z }
         }
 
-        pub fn neighbours(self) -> [Position; 6] {
-            [
-                self + Orientation::North,
-                self + Orientation::South,
-                self + Orientation::East,
-                self + Orientation::West,
-                self + Orientation::Up,
-                self + Orientation::Down,
-            ]
+        // neighbour in the given orientation, None outside of the range
+        pub fn step(self, ori: Orientation) -> Option<Position> {
+            let (dx, dy, dz) = ori.vector();
+            Some(Position::new(
+                self.x.checked_add(dx)?,
+                self.y.checked_add(dy)?,
+                self.z.checked_add(dz)?,
+            ))
         }
     }
 
@@ -94,11 +195,103 @@ pub mod brick {
         Down,
     }
 
+    impl Orientation {
+        pub fn from_char(c: char) -> Option<Orientation> {
+            match c {
+                'N' => Some(Orientation::North),
+                'S' => Some(Orientation::South),
+                'E' => Some(Orientation::East),
+                'W' => Some(Orientation::West),
+                'U' => Some(Orientation::Up),
+                'D' => Some(Orientation::Down),
+                _ => None,
+            }
+        }
+
+        pub fn to_char(self) -> char {
+            match self {
+                Orientation::North => 'N',
+                Orientation::South => 'S',
+                Orientation::East => 'E',
+                Orientation::West => 'W',
+                Orientation::Up => 'U',
+                Orientation::Down => 'D',
+            }
+        }
+
+        pub fn opposite(self) -> Orientation {
+            match self {
+                Orientation::North => Orientation::South,
+                Orientation::South => Orientation::North,
+                Orientation::East => Orientation::West,
+                Orientation::West => Orientation::East,
+                Orientation::Up => Orientation::Down,
+                Orientation::Down => Orientation::Up,
+            }
+        }
+
+        pub fn is_perpendicular(self, other: Orientation) -> bool {
+            self != other && self != other.opposite()
+        }
+
+        pub fn vector(self) -> (Coord, Coord, Coord) {
+            match self {
+                Orientation::North => (1, 0, 0),
+                Orientation::South => (-1, 0, 0),
+                Orientation::East => (0, 1, 0),
+                Orientation::West => (0, -1, 0),
+                Orientation::Up => (0, 0, 1),
+                Orientation::Down => (0, 0, -1),
+            }
+        }
+
+        pub fn from_vector(vec: (Coord, Coord, Coord)) -> Option<Orientation> {
+            match vec {
+                (1, 0, 0) => Some(Orientation::North),
+                (-1, 0, 0) => Some(Orientation::South),
+                (0, 1, 0) => Some(Orientation::East),
+                (0, -1, 0) => Some(Orientation::West),
+                (0, 0, 1) => Some(Orientation::Up),
+                (0, 0, -1) => Some(Orientation::Down),
+                _ => None,
+            }
+        }
+
+        // up of the frame a chain starts with if nothing else is given
+        pub fn default_up(self) -> Orientation {
+            match self {
+                Orientation::Up | Orientation::Down => Orientation::North,
+                _ => Orientation::Up,
+            }
+        }
+
+        // None for parallel orientations
+        pub fn cross(self, other: Orientation) -> Option<Orientation> {
+            let (ax, ay, az) = self.vector();
+            let (bx, by, bz) = other.vector();
+            Orientation::from_vector((ay * bz - az * by, az * bx - ax * bz, ax * by - ay * bx))
+        }
+    }
+
+    pub const ORIENTATIONS: [Orientation; 6] = [
+        Orientation::North,
+        Orientation::South,
+        Orientation::East,
+        Orientation::West,
+        Orientation::Up,
+        Orientation::Down,
+    ];
+
     #[derive(Copy, Clone, Debug)]
     pub struct Brick {
         pub coordinates: Position,
         pub form: Form,
         pub orientation: Orientation,
+        // orientation before the last turn, reference for twisted joints
+        pub previous: Option<Orientation>,
+        // together with the orientation the frame of the brick, always
+        // perpendicular to the orientation
+        pub up: Orientation,
     }
 
     impl Brick {
@@ -107,29 +300,57 @@ pub mod brick {
                 orientation: orient,
                 coordinates: crd,
                 form: frm,
+                previous: None,
+                up: orient.default_up(),
             }
         }
 
-        pub fn next_straight(&self) -> Brick {
-            Brick {
+        // same brick with another up, ignored unless perpendicular
+        pub fn with_up(mut self, up: Orientation) -> Brick {
+            if up.is_perpendicular(self.orientation) {
+                self.up = up;
+            }
+            self
+        }
+
+        // left of the frame, None only for a broken frame
+        pub fn left(&self) -> Option<Orientation> {
+            self.orientation.cross(self.up)
+        }
+
+        // None at the end of the coordinate range, as for all successors
+        pub fn next_straight(&self) -> Option<Brick> {
+            Some(Brick {
                 orientation: self.orientation,
-                coordinates: self.coordinates + self.orientation,
+                coordinates: self.coordinates.step(self.orientation)?,
                 form: Form::Straight,
-            }
+                previous: self.previous,
+                up: self.up,
+            })
         }
 
-        pub fn next_turn_orientation(&self, ori: &Orientation) -> Brick {
-            Brick {
+        /*
+         * the frame is rotated along with the turn: up stays if it is the
+         * axis of the rotation, otherwise it is turned by 90 degrees too
+         */
+        pub fn next_turn_orientation(&self, ori: &Orientation) -> Option<Brick> {
+            let up = if self.up == *ori {
+                self.orientation.opposite()
+            } else if self.up == ori.opposite() {
+                self.orientation
+            } else {
+                self.up
+            };
+            Some(Brick {
                 orientation: *ori,
-                coordinates: self.coordinates + self.orientation,
+                coordinates: self.coordinates.step(self.orientation)?,
                 form: Form::Turn,
-            }
+                previous: Some(self.orientation),
+                up,
+            })
         }
 
-        pub fn next_turn(&self) -> [Brick; 4] {
-            // FIXME: use next_turn_orientation in here!
-            let coord = self.coordinates + self.orientation;
-
+        pub fn next_turn(&self) -> Vec<Brick> {
             let (or0, or1, or2, or3) = match self.orientation {
                 Orientation::North | Orientation::South => (
                     Orientation::East,
@@ -151,28 +372,72 @@ pub mod brick {
                 ),
             };
 
-            [
-                Brick {
-                    orientation: or0,
-                    coordinates: coord,
-                    form: Form::Turn,
-                },
-                Brick {
-                    orientation: or1,
-                    coordinates: coord,
-                    form: Form::Turn,
-                },
-                Brick {
-                    orientation: or2,
-                    coordinates: coord,
-                    form: Form::Turn,
-                },
-                Brick {
-                    orientation: or3,
-                    coordinates: coord,
-                    form: Form::Turn,
-                },
-            ]
+            [or0, or1, or2, or3]
+                .iter()
+                .filter_map(|ori| self.next_turn_orientation(ori))
+                .collect()
+        }
+
+        // successor with the given form, whether the joint allows it or not
+        pub fn next_orientation(&self, ori: &Orientation, frm: Form) -> Option<Brick> {
+            let mut brk = if *ori == self.orientation {
+                self.next_straight()?
+            } else {
+                self.next_turn_orientation(ori)?
+            };
+            brk.form = frm;
+            Some(brk)
+        }
+
+        // all successors with the given form
+        pub fn next(&self, frm: Form) -> Vec<Brick> {
+            ORIENTATIONS
+                .iter()
+                .filter(|ori| frm.allows(self, **ori))
+                .filter_map(|ori| self.next_orientation(ori, frm))
+                .collect()
+        }
+
+        // the brick without its coordinates, given by the id of its cell
+        pub fn pack(&self, cell: usize) -> Packed {
+            assert!(cell < Packed::CELLS, "too many cells to pack a brick");
+            let previous = match self.previous {
+                None => 6,
+                Some(ori) => ori as u32,
+            };
+            Packed(
+                (cell as u32) << 13
+                    | (self.form as u32) << 9
+                    | previous << 6
+                    | (self.up as u32) << 3
+                    | self.orientation as u32,
+            )
+        }
+    }
+
+    /*
+     * a brick in 32 bits: 19 bits cell id, 4 bits form, 3 bits each for
+     * the previous orientation (6: none), up and orientation
+     */
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct Packed(u32);
+
+    impl Packed {
+        pub const CELLS: usize = 1 << 19;
+
+        pub fn cell(self) -> usize {
+            (self.0 >> 13) as usize
+        }
+
+        pub fn unpack(self, coordinates: Position) -> Brick {
+            let bits = |shift: u32, width: u32| ((self.0 >> shift) & ((1 << width) - 1)) as usize;
+            Brick {
+                coordinates,
+                form: Form::from_index(bits(9, 4)),
+                orientation: ORIENTATIONS[bits(0, 3)],
+                previous: ORIENTATIONS.get(bits(6, 3)).copied(),
+                up: ORIENTATIONS[bits(3, 3)],
+            }
         }
     }
 }
@@ -201,4 +466,134 @@ mod tests {
         let brk = Brick::new(Position::new(0, 0, 0), Orientation::Down, Form::Straight);
         assert_eq!(0, brk.coordinates.x);
     }
+
+    #[test]
+    fn test_step() {
+        // no wraparound at the end of the coordinate range
+        let pos = Position::new(Coord::MAX, 0, Coord::MIN);
+        assert_eq!(None, pos.step(Orientation::North));
+        assert_eq!(None, pos.step(Orientation::Down));
+        assert_eq!(
+            Some(Position::new(Coord::MAX, 0, Coord::MIN + 1)),
+            pos.step(Orientation::Up)
+        );
+        assert_eq!(
+            4,
+            ORIENTATIONS.iter().filter_map(|ori| pos.step(*ori)).count()
+        );
+
+        let brk = Brick::new(pos, Orientation::North, Form::Straight);
+        assert!(brk.next_straight().is_none());
+        assert!(brk.next_turn().is_empty());
+        assert!(brk.next(Form::Free).is_empty());
+        let brk = Brick::new(pos, Orientation::East, Form::Straight);
+        assert_eq!(4, brk.next_turn().len());
+    }
+
+    #[test]
+    fn test_orientation() {
+        assert_eq!(
+            Some(Orientation::Up),
+            Orientation::North.cross(Orientation::East)
+        );
+        assert_eq!(
+            Some(Orientation::Down),
+            Orientation::East.cross(Orientation::North)
+        );
+        assert_eq!(
+            Some(Orientation::North),
+            Orientation::East.cross(Orientation::Up)
+        );
+        assert_eq!(None, Orientation::West.cross(Orientation::East));
+        assert!(Orientation::Up.is_perpendicular(Orientation::South));
+        assert!(!Orientation::Up.is_perpendicular(Orientation::Down));
+        assert_eq!(Some(Orientation::Down), Orientation::from_char('D'));
+        assert_eq!('W', Orientation::West.to_char());
+    }
+
+    #[test]
+    fn test_forms() {
+        let brk = Brick::new(Position::new(0, 0, 0), Orientation::North, Form::Straight);
+        assert_eq!(1, brk.next(Form::Straight).len());
+        assert_eq!(4, brk.next(Form::Turn).len());
+        assert_eq!(5, brk.next(Form::Free).len());
+        // no turn yet, every perpendicular orientation is fine
+        assert_eq!(4, brk.next(Form::Zigzag).len());
+
+        // turned from north to east
+        let brk = brk.next_turn_orientation(&Orientation::East).unwrap();
+        assert_eq!(Some(Orientation::North), brk.previous);
+        let planar: Vec<Orientation> = brk
+            .next(Form::Planar)
+            .iter()
+            .map(|bb| bb.orientation)
+            .collect();
+        assert_eq!(vec![Orientation::North, Orientation::South], planar);
+        let twisted: Vec<Orientation> = brk
+            .next(Form::Twisted)
+            .iter()
+            .map(|bb| bb.orientation)
+            .collect();
+        assert_eq!(vec![Orientation::Up, Orientation::Down], twisted);
+        assert_eq!(Orientation::North, brk.next(Form::Zigzag)[0].orientation);
+        assert_eq!(Orientation::South, brk.next(Form::Back)[0].orientation);
+        assert_eq!(1, brk.next(Form::Back).len());
+
+        // straight bricks keep the reference
+        let brk = brk.next(Form::Straight)[0];
+        assert_eq!(Some(Orientation::North), brk.previous);
+        assert_eq!(Form::Straight, brk.form);
+        assert_eq!(Position::new(1, 1, 0), brk.coordinates);
+
+        for cc in "STXPOZU".chars() {
+            assert_eq!(cc, Form::from_char(cc).unwrap().to_char());
+        }
+        assert_eq!(None, Form::from_char('N'));
+    }
+
+    #[test]
+    fn test_frame() {
+        let brk = Brick::new(Position::new(0, 0, 0), Orientation::North, Form::Straight);
+        assert_eq!(Orientation::Up, brk.up);
+        // facing north, seen from above
+        assert_eq!(Some(Orientation::West), brk.left());
+        assert_eq!(Orientation::West, brk.next(Form::Left)[0].orientation);
+        assert_eq!(Orientation::East, brk.next(Form::Right)[0].orientation);
+        assert_eq!(1, brk.next(Form::Right).len());
+
+        // turning upwards tilts the frame backwards
+        let brk = brk.next_turn_orientation(&Orientation::Up).unwrap();
+        assert_eq!(Orientation::South, brk.up);
+        assert_eq!(Some(Orientation::West), brk.left());
+        // turning around up keeps it
+        let brk = brk.next_turn_orientation(&Orientation::East).unwrap();
+        assert_eq!(Orientation::South, brk.up);
+        assert_eq!(Orientation::Up, brk.next(Form::Left)[0].orientation);
+
+        let brk = brk.with_up(Orientation::West);
+        assert_eq!(Orientation::South, brk.up);
+        let brk = brk.with_up(Orientation::Down);
+        assert_eq!(Orientation::Down, brk.up);
+    }
+
+    #[test]
+    fn test_packed() {
+        let pos = Position::new(4, -2, 7);
+        let brk = Brick::new(pos, Orientation::North, Form::Turn);
+        let bricks = [
+            brk,
+            brk.next_turn()[2]
+                .next_orientation(&Orientation::East, Form::Right)
+                .unwrap(),
+            brk.with_up(Orientation::West).next(Form::Back)[0],
+        ];
+        for (ii, brk) in bricks.iter().enumerate() {
+            let packed = brk.pack(ii * 1000);
+            assert_eq!(ii * 1000, packed.cell());
+            let other = packed.unpack(brk.coordinates);
+            assert_eq!(format!("{:?}", brk), format!("{:?}", other));
+        }
+        assert_eq!(Packed::CELLS - 1, brk.pack(Packed::CELLS - 1).cell());
+        assert_eq!(None, brk.pack(0).unpack(pos).previous);
+    }
 }
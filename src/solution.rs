@@ -0,0 +1,96 @@
+use crate::area::{Coord, Position};
+use crate::instructions;
+use crate::snake::brick::{Brick, Orientation};
+use crate::symmetry::{canonical, canonical_ring, Symmetry};
+use crate::verify::orientation_switches;
+use std::vec::Vec;
+
+// a complete folding, bricks ordered from start to end
+#[derive(Clone, Debug)]
+pub struct Solution {
+    pub bricks: Vec<Brick>,
+}
+
+impl Solution {
+    pub fn new(bricks: Vec<Brick>) -> Solution {
+        Solution { bricks }
+    }
+
+    // as in the solution lines of a puzzle file
+    pub fn orientations(&self) -> String {
+        orientation_switches(&self.bricks)
+    }
+
+    // first and last brick and orientation of the straight segments
+    pub fn segments(&self) -> Vec<(usize, usize, Orientation)> {
+        instructions::segments(&self.bricks)
+    }
+
+    pub fn cells(&self) -> Vec<Position> {
+        self.bricks.iter().map(|brk| brk.coordinates).collect()
+    }
+
+    /*
+     * the same for solutions equal up to the symmetries of the box with
+     * corner lower and reversal (and rotation, for a closed ring)
+     */
+    pub fn key(
+        &self,
+        lower: Position,
+        symmetries: &[Symmetry],
+        cyclic: bool,
+    ) -> Vec<(Coord, Coord, Coord)> {
+        let moved: Vec<Brick> = self
+            .bricks
+            .iter()
+            .map(|brk| {
+                let pos = brk.coordinates;
+                let mut brk = *brk;
+                brk.coordinates = Position::new(pos.x - lower.x, pos.y - lower.y, pos.z - lower.z);
+                brk
+            })
+            .collect();
+        if cyclic {
+            canonical_ring(&moved, symmetries)
+        } else {
+            canonical(&moved, symmetries)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_solution() {
+        let mut parser = Parser::new(
+            "area box 2 2 1
+chain 4 STTS
+path N
+start 0 0 0
+",
+        );
+        parser.path.fold(false);
+        let solution = parser.path.solutions(false).next().unwrap();
+        assert_eq!("NES", solution.orientations());
+        assert_eq!(
+            vec![
+                (0, 0, Orientation::North),
+                (1, 1, Orientation::East),
+                (2, 3, Orientation::South)
+            ],
+            solution.segments()
+        );
+        assert_eq!(
+            vec![
+                Position::new(0, 0, 0),
+                Position::new(1, 0, 0),
+                Position::new(1, 1, 0),
+                Position::new(0, 1, 0)
+            ],
+            solution.cells()
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use crate::render::palette;
+use crate::snake::brick::Brick;
+use crate::snake::chain::Chain;
+use std::vec::Vec;
+
+const SCALE: f64 = 40.0;
+const MARGIN: f64 = 10.0;
+
+/*
+ * isometric view from north-east above: x (north) runs to the lower
+ * right, y (east) to the lower left and z upwards, as seen by someone
+ * standing there
+ */
+fn project(x: f64, y: f64, z: f64) -> (f64, f64) {
+    let cos30 = 3f64.sqrt() / 2.0;
+    ((x - y) * cos30 * SCALE, ((x + y) * 0.5 - z) * SCALE)
+}
+
+fn polygon(corners: &[(f64, f64, f64)], offset: (f64, f64), fill: &str, stroke: f64) -> String {
+    let points: Vec<String> = corners
+        .iter()
+        .map(|cc| {
+            let (u, v) = project(cc.0, cc.1, cc.2);
+            format!("{:.1},{:.1}", u - offset.0, v - offset.1)
+        })
+        .collect();
+    format!(
+        "<polygon points=\"{}\" fill=\"{}\" stroke=\"#202020\" stroke-width=\"{}\"/>",
+        points.join(" "),
+        fill,
+        stroke
+    )
+}
+
+// visible faces of the unit cube at the given corner: top, north, east
+fn faces(x: f64, y: f64, z: f64) -> [[(f64, f64, f64); 4]; 3] {
+    [
+        [
+            (x, y, z + 1.0),
+            (x + 1.0, y, z + 1.0),
+            (x + 1.0, y + 1.0, z + 1.0),
+            (x, y + 1.0, z + 1.0),
+        ],
+        [
+            (x + 1.0, y, z),
+            (x + 1.0, y + 1.0, z),
+            (x + 1.0, y + 1.0, z + 1.0),
+            (x + 1.0, y, z + 1.0),
+        ],
+        [
+            (x, y + 1.0, z),
+            (x + 1.0, y + 1.0, z),
+            (x + 1.0, y + 1.0, z + 1.0),
+            (x, y + 1.0, z + 1.0),
+        ],
+    ]
+}
+
+/*
+ * one cube per brick, in the colour of the brick or coloured along the
+ * chain from red to violet; bricks starting a new segment are outlined
+ * thicker
+ */
+pub fn solution_svg(chain: &Chain, bricks: &[Brick]) -> String {
+    let mut lower = (f64::INFINITY, f64::INFINITY);
+    let mut upper = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for brk in bricks {
+        let pos = brk.coordinates;
+        for face in faces(pos.x as f64, pos.y as f64, pos.z as f64).iter() {
+            for cc in face.iter() {
+                let (u, v) = project(cc.0, cc.1, cc.2);
+                lower = (f64::min(lower.0, u), f64::min(lower.1, v));
+                upper = (f64::max(upper.0, u), f64::max(upper.1, v));
+            }
+        }
+    }
+    let offset = (lower.0 - MARGIN, lower.1 - MARGIN);
+    let width = upper.0 - lower.0 + 2.0 * MARGIN;
+    let height = upper.1 - lower.1 + 2.0 * MARGIN;
+
+    // painter's algorithm: far cubes first
+    let mut order: Vec<usize> = (0..bricks.len()).collect();
+    order.sort_by_key(|ii| {
+        let pos = bricks[*ii].coordinates;
+        i32::from(pos.x) + i32::from(pos.y) + i32::from(pos.z)
+    });
+
+    let mut output = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"0 0 {:.1} {:.1}\">\n",
+        width.ceil(),
+        height.ceil(),
+        width,
+        height
+    );
+    for ii in order {
+        let brk = &bricks[ii];
+        let segment_start = ii == 0 || bricks[ii - 1].orientation != brk.orientation;
+        let stroke = if segment_start { 3.0 } else { 0.5 };
+        let hue = 300.0 * ii as f64 / bricks.len().max(2) as f64;
+        let colour = chain.colour(ii).and_then(palette).map(|(_, hsl)| hsl);
+
+        output.push_str(&format!("<g><title>brick {}</title>\n", ii + 1));
+        let pos = brk.coordinates;
+        let shades = [65, 50, 38];
+        for (face, shade) in faces(pos.x as f64, pos.y as f64, pos.z as f64)
+            .iter()
+            .zip(shades.iter())
+        {
+            let fill = match colour {
+                // darker sides the same way as the shades
+                Some((hh, ss, ll)) => format!("hsl({},{}%,{}%)", hh, ss, ll * shade / 65),
+                None => format!("hsl({:.0},70%,{}%)", hue, shade),
+            };
+            output.push_str(&polygon(face, offset, &fill, stroke));
+            output.push('\n');
+        }
+        output.push_str("</g>\n");
+    }
+    output.push_str("</svg>\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::test_support::cube_folding;
+    use crate::verify::{parse_orientations, replay};
+
+    #[test]
+    fn test_svg() {
+        let (parser, bricks) = cube_folding();
+        let output = solution_svg(&parser.path.chain, &bricks);
+        assert!(output.starts_with("<svg "));
+        assert!(output.ends_with("</svg>\n"));
+        assert_eq!(27, output.matches("<g>").count());
+        assert_eq!(3 * 27, output.matches("<polygon").count());
+        // one thick outline per segment
+        assert_eq!(3 * 17, output.matches("stroke-width=\"3\"").count());
+        // the closest cube is drawn last
+        let last = output.rfind("<title>").unwrap();
+        assert!(output[last..].starts_with("<title>brick 27</title>"));
+        assert!(!output.contains("hsl(0,75%,50%)"));
+
+        let mut chain = parser.path.chain.copy();
+        chain.colours = vec![Some('r'), None, Some('w')];
+        let output = solution_svg(&chain, &bricks);
+        assert_eq!(3, output.matches("hsl(0,75%,").count());
+        assert!(output.contains("hsl(0,75%,50%)"));
+        assert!(output.contains("hsl(0,0%,92%)"));
+    }
+
+    #[test]
+    fn test_handedness() {
+        // heading north towards the viewer, a left turn runs to the right
+        let parser = Parser::new(
+            "area box 2 2 1
+chain 3 SLS
+start 0 1 0
+",
+        );
+        let bricks = replay(
+            &parser.path.chain,
+            parser.start,
+            None,
+            &parse_orientations("NW"),
+        )
+        .unwrap();
+        let centre = |brk: &Brick| {
+            let pos = brk.coordinates;
+            project(pos.x as f64 + 0.5, pos.y as f64 + 0.5, pos.z as f64 + 0.5)
+        };
+        assert!(centre(&bricks[1]).1 > centre(&bricks[0]).1);
+        assert!(centre(&bricks[2]).0 > centre(&bricks[1]).0);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::area::{Area, Coord, Position};
+use crate::snake::brick::Brick;
+use std::vec::Vec;
+
+/*
+ * rotation or reflection of a box [0, dims) onto itself: coordinate
+ * axis ii of the image is axis perm[ii] of the original, mirrored if
+ * flip[ii] is set
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Symmetry {
+    perm: [usize; 3],
+    flip: [bool; 3],
+    dims: [Coord; 3],
+}
+
+const PERMUTATIONS: [[usize; 3]; 6] = [
+    [0, 1, 2],
+    [0, 2, 1],
+    [1, 0, 2],
+    [1, 2, 0],
+    [2, 0, 1],
+    [2, 1, 0],
+];
+
+impl Symmetry {
+    // all symmetries of the box with the given edge lengths
+    pub fn all(dims: (Coord, Coord, Coord)) -> Vec<Symmetry> {
+        let dims = [dims.0, dims.1, dims.2];
+        let mut result: Vec<Symmetry> = Vec::new();
+        for perm in PERMUTATIONS.iter() {
+            if (0..3).any(|ii| dims[perm[ii]] != dims[ii]) {
+                continue;
+            }
+            for bits in 0..8 {
+                result.push(Symmetry {
+                    perm: *perm,
+                    flip: [bits & 1 != 0, bits & 2 != 0, bits & 4 != 0],
+                    dims,
+                });
+            }
+        }
+        result
+    }
+
+    // no reflection, i.e. the determinant is 1
+    pub fn is_rotation(&self) -> bool {
+        let [aa, bb, cc] = self.perm;
+        let inversions = (aa > bb) as usize + (aa > cc) as usize + (bb > cc) as usize;
+        let flips = self.flip.iter().filter(|ff| **ff).count();
+        (inversions + flips).is_multiple_of(2)
+    }
+
+    /*
+     * symmetries of the bounding box of an area, moved to the origin; the
+     * identity only unless the area is a box on a lattice with the
+     * symmetries of a box; the mirror image of a folding of a handed
+     * chain is no folding of the same chain, so no reflections then
+     */
+    pub fn of_area(area: &Area, handed: bool) -> Vec<Symmetry> {
+        let dims = match area.bounds {
+            None => (1, 1, 1),
+            Some((lower, upper)) => (
+                upper.x - lower.x + 1,
+                upper.y - lower.y + 1,
+                upper.z - lower.z + 1,
+            ),
+        };
+        let mut symmetries = Symmetry::all(dims);
+        if !area.is_box() || !area.lattice.has_box_symmetry() {
+            symmetries.truncate(1);
+        }
+        if handed {
+            symmetries.retain(|sym| sym.is_rotation());
+        }
+        symmetries
+    }
+
+    pub fn position(&self, pos: Position) -> Position {
+        let coords = [pos.x, pos.y, pos.z];
+        let mut image = [0; 3];
+        for ii in 0..3 {
+            let cc = coords[self.perm[ii]];
+            image[ii] = if self.flip[ii] {
+                self.dims[ii] - 1 - cc
+            } else {
+                cc
+            };
+        }
+        Position::new(image[0], image[1], image[2])
+    }
+}
+
+/*
+ * representative of a folding up to symmetries of the box and reversal
+ * of the chain: the lexicographically smallest sequence of cells
+ */
+pub fn canonical(bricks: &[Brick], symmetries: &[Symmetry]) -> Vec<(Coord, Coord, Coord)> {
+    let forward: Vec<Position> = bricks.iter().map(|brk| brk.coordinates).collect();
+    let mut backward = forward.to_vec();
+    backward.reverse();
+
+    let mut best: Option<Vec<(Coord, Coord, Coord)>> = None;
+    for cells in [forward, backward].iter() {
+        for sym in symmetries {
+            let image: Vec<(Coord, Coord, Coord)> = cells
+                .iter()
+                .map(|pos| {
+                    let pp = sym.position(*pos);
+                    (pp.x, pp.y, pp.z)
+                })
+                .collect();
+            if best.as_ref().is_none_or(|bb| image < *bb) {
+                best = Some(image);
+            }
+        }
+    }
+    best.unwrap_or_default()
+}
+
+// like canonical, but every brick of a closed ring can be the first one
+pub fn canonical_ring(bricks: &[Brick], symmetries: &[Symmetry]) -> Vec<(Coord, Coord, Coord)> {
+    let nn = bricks.len();
+    let mut best: Option<Vec<(Coord, Coord, Coord)>> = None;
+    for rr in 0..nn {
+        let rotated: Vec<Brick> = (0..nn).map(|ii| bricks[(rr + ii) % nn]).collect();
+        let image = canonical(&rotated, symmetries);
+        if best.as_ref().is_none_or(|bb| image < *bb) {
+            best = Some(image);
+        }
+    }
+    best.unwrap_or_default()
+}
+
+// cells which are the smallest of their orbit, enough as start cells
+pub fn representative_cells(dims: (Coord, Coord, Coord), symmetries: &[Symmetry]) -> Vec<Position> {
+    let mut cells: Vec<Position> = Vec::new();
+    for x in 0..dims.0 {
+        for y in 0..dims.1 {
+            for z in 0..dims.2 {
+                let pos = Position::new(x, y, z);
+                let key = (x, y, z);
+                if symmetries.iter().all(|sym| {
+                    let pp = sym.position(pos);
+                    (pp.x, pp.y, pp.z) >= key
+                }) {
+                    cells.push(pos);
+                }
+            }
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lattice::{Cubic, Lattice};
+    use crate::snake::brick::Orientation;
+    use crate::snake::chain::Form;
+
+    #[test]
+    fn test_symmetries() {
+        assert_eq!(48, Symmetry::all((3, 3, 3)).len());
+        assert_eq!(16, Symmetry::all((3, 3, 2)).len());
+        assert_eq!(8, Symmetry::all((4, 3, 2)).len());
+        let rotations = Symmetry::all((3, 3, 3))
+            .iter()
+            .filter(|sym| sym.is_rotation())
+            .count();
+        assert_eq!(24, rotations);
+
+        for sym in Symmetry::all((3, 3, 2)) {
+            let pos = sym.position(Position::new(2, 1, 0));
+            assert!(pos.x >= 0 && pos.x < 3 && pos.y >= 0 && pos.y < 3);
+            assert!(pos.z >= 0 && pos.z < 2);
+            // neighbours stay neighbours
+            let next = sym.position(Position::new(2, 1, 0).step(Orientation::West).unwrap());
+            assert!(Cubic.neighbours(pos).contains(&next));
+        }
+    }
+
+    #[test]
+    fn test_representatives() {
+        // corner, edge, face and centre
+        assert_eq!(
+            4,
+            representative_cells((3, 3, 3), &Symmetry::all((3, 3, 3))).len()
+        );
+        assert_eq!(
+            4,
+            representative_cells((4, 4, 4), &Symmetry::all((4, 4, 4))).len()
+        );
+    }
+
+    #[test]
+    fn test_canonical() {
+        let syms = Symmetry::all((2, 2, 1));
+        let aa = [
+            Brick::new(Position::new(0, 0, 0), Orientation::North, Form::Straight),
+            Brick::new(Position::new(1, 0, 0), Orientation::East, Form::Turn),
+            Brick::new(Position::new(1, 1, 0), Orientation::South, Form::Turn),
+            Brick::new(Position::new(0, 1, 0), Orientation::South, Form::Straight),
+        ];
+        // mirrored and reversed
+        let bb = [
+            Brick::new(Position::new(1, 0, 0), Orientation::South, Form::Straight),
+            Brick::new(Position::new(0, 0, 0), Orientation::East, Form::Turn),
+            Brick::new(Position::new(0, 1, 0), Orientation::North, Form::Turn),
+            Brick::new(Position::new(1, 1, 0), Orientation::North, Form::Straight),
+        ];
+        assert_eq!(canonical(&aa, &syms), canonical(&bb, &syms));
+        assert_eq!(
+            vec![(0, 0, 0), (0, 1, 0), (1, 1, 0), (1, 0, 0)],
+            canonical(&aa, &syms)
+        );
+    }
+}
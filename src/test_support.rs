@@ -0,0 +1,22 @@
+use crate::parser::Parser;
+use crate::snake::brick::Brick;
+use crate::verify::{parse_orientations, replay};
+use std::vec::Vec;
+
+// the classic 3x3x3 snake and one of its foldings, for the tests of the outputs
+pub fn cube_folding() -> (Parser, Vec<Brick>) {
+    let parser = Parser::new(
+        "area cube 3
+chain 27 SSTTTSTTSTTTSTSTTTTSTSTSTSS
+start 0 0 0
+",
+    );
+    let bricks = replay(
+        &parser.path.chain,
+        parser.start,
+        parser.up,
+        &parse_orientations("NESUEDNUWSEDEUWNE"),
+    )
+    .unwrap();
+    (parser, bricks)
+}
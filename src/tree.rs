@@ -1,42 +1,116 @@
-use std::rc::Rc;
-
-pub struct Node<T> {
-    pub father: Option<Rc<Node<T>>>,
-    pub value: T,
+use std::mem::size_of;
+use std::vec::Vec;
+
+// father of a root
+const ROOT: u32 = u32::MAX;
+
+/*
+ * nodes of a tree that only grows at its leaves, stored in one vector;
+ * a node is its index and knows the index of its father
+ */
+pub struct Tree<T> {
+    fathers: Vec<u32>,
+    values: Vec<T>,
 }
 
-pub struct TreeIterator<T> {
-    curr: Option<Rc<Node<T>>>,
-}
+impl<T: Copy> Tree<T> {
+    pub fn new() -> Tree<T> {
+        Tree {
+            fathers: Vec::new(),
+            values: Vec::new(),
+        }
+    }
 
-impl<T> Iterator for TreeIterator<T> {
-    type Item = Rc<Node<T>>;
+    pub fn add(&mut self, father: Option<usize>, value: T) -> usize {
+        self.fathers.push(match father {
+            None => ROOT,
+            Some(ff) => ff as u32,
+        });
+        self.values.push(value);
+        self.values.len() - 1
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        // set return value
-        let retval = match &self.curr {
-            None => None,
-            Some(cc) => Some(Rc::clone(&cc)),
-        };
-
-        // update current value
-        self.curr = match &self.curr {
-            None => None,
-            Some(cc) => match &cc.father {
-                None => None,
-                Some(ff) => Some(Rc::clone(&ff)),
-            },
-        };
-
-        retval
+    pub fn value(&self, node: usize) -> T {
+        self.values[node]
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.values.iter_mut()
+    }
+
+    pub fn father(&self, node: usize) -> Option<usize> {
+        match self.fathers[node] {
+            ROOT => None,
+            ff => Some(ff as usize),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    // memory taken by the nodes
+    pub fn bytes(&self) -> usize {
+        self.fathers.capacity() * size_of::<u32>() + self.values.capacity() * size_of::<T>()
     }
-}
 
-impl<T> TreeIterator<T> {
-    pub fn new(startnode: Rc<Node<T>>) -> TreeIterator<T> {
-        TreeIterator::<T> {
-            curr: Some(Rc::clone(&startnode)),
+    // values from node up to its root
+    pub fn iter(&self, node: usize) -> TreeIterator<'_, T> {
+        TreeIterator {
+            tree: self,
+            curr: Some(node),
+        }
+    }
+
+    /*
+     * drops all nodes that are no ancestor of one of the leaves, returns
+     * the new indices of the leaves; fathers are always stored before
+     * their children, so one pass from the back finds the ancestors
+     */
+    pub fn collect(&mut self, leaves: &[usize]) -> Vec<usize> {
+        let mut alive = vec![false; self.len()];
+        for leaf in leaves {
+            alive[*leaf] = true;
+        }
+        for node in (0..self.len()).rev() {
+            if let (true, Some(ff)) = (alive[node], self.father(node)) {
+                alive[ff] = true;
+            }
+        }
+        let mut index: Vec<u32> = vec![0; self.len()];
+        let mut kept = 0;
+        for node in 0..self.len() {
+            if !alive[node] {
+                continue;
+            }
+            index[node] = kept as u32;
+            self.fathers[kept] = match self.father(node) {
+                None => ROOT,
+                Some(ff) => index[ff],
+            };
+            self.values[kept] = self.values[node];
+            kept += 1;
         }
+        self.fathers.truncate(kept);
+        self.values.truncate(kept);
+        self.fathers.shrink_to_fit();
+        self.values.shrink_to_fit();
+        leaves.iter().map(|leaf| index[*leaf] as usize).collect()
+    }
+}
+
+pub struct TreeIterator<'a, T> {
+    tree: &'a Tree<T>,
+    curr: Option<usize>,
+}
+
+impl<'a, T: Copy> Iterator for TreeIterator<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.curr?;
+        self.curr = self.tree.father(node);
+        Some(self.tree.value(node))
     }
 }
 
@@ -46,88 +120,64 @@ mod tests {
 
     #[test]
     fn test_int_tree() {
-        let a = Rc::new(Node::<i32> {
-            father: None,
-            value: 0,
-        });
-        let b = Rc::new(Node::<i32> {
-            father: Some(Rc::clone(&a)),
-            value: 1,
-        });
-        let c = Rc::new(Node::<i32> {
-            father: Some(Rc::clone(&a)),
-            value: 2,
-        });
-        let d = Rc::new(Node::<i32> {
-            father: Some(Rc::clone(&b)),
-            value: 3,
-        });
-        let e = Rc::new(Node::<i32> {
-            father: Some(Rc::clone(&c)),
-            value: 4,
-        });
-        let f = Rc::new(Node::<i32> {
-            father: Some(Rc::clone(&c)),
-            value: 5,
-        });
-        let g = Rc::new(Node::<i32> {
-            father: Some(Rc::clone(&f)),
-            value: 6,
-        });
-
-        let mut r = TreeIterator::new(Rc::clone(&g));
-        assert_eq!(r.next().unwrap().value, 6);
-        assert_eq!(r.next().unwrap().value, 5);
-        assert_eq!(r.next().unwrap().value, 2);
-        assert_eq!(r.next().unwrap().value, 0);
-
-        r = TreeIterator::new(Rc::clone(&e));
-        assert_eq!(r.next().unwrap().value, 4);
-        assert_eq!(r.next().unwrap().value, 2);
-        assert_eq!(r.next().unwrap().value, 0);
-
-        r = TreeIterator::new(Rc::clone(&d));
-        assert_eq!(r.next().unwrap().value, 3);
-        assert_eq!(r.next().unwrap().value, 1);
-        assert_eq!(r.next().unwrap().value, 0);
+        let mut tree = Tree::<i32>::new();
+        let a = tree.add(None, 0);
+        let b = tree.add(Some(a), 1);
+        let c = tree.add(Some(a), 2);
+        let d = tree.add(Some(b), 3);
+        let e = tree.add(Some(c), 4);
+        let f = tree.add(Some(c), 5);
+        let g = tree.add(Some(f), 6);
+
+        let mut r = tree.iter(g);
+        assert_eq!(r.next().unwrap(), 6);
+        assert_eq!(r.next().unwrap(), 5);
+        assert_eq!(r.next().unwrap(), 2);
+        assert_eq!(r.next().unwrap(), 0);
+        assert_eq!(r.next(), None);
+
+        r = tree.iter(e);
+        assert_eq!(r.next().unwrap(), 4);
+        assert_eq!(r.next().unwrap(), 2);
+        assert_eq!(r.next().unwrap(), 0);
+
+        r = tree.iter(d);
+        assert_eq!(r.next().unwrap(), 3);
+        assert_eq!(r.next().unwrap(), 1);
+        assert_eq!(r.next().unwrap(), 0);
     }
 
     #[test]
-    fn test_string_tree() {
-        let a = Rc::new(Node::<String> {
-            father: None,
-            value: "a".to_string(),
-        });
-        let b = Rc::new(Node::<String> {
-            father: Some(Rc::clone(&a)),
-            value: "b".to_string(),
-        });
-
-        let mut r = TreeIterator::new(Rc::clone(&b));
-        assert_eq!(r.next().unwrap().value, "b");
-        assert_eq!(r.next().unwrap().value, "a");
+    fn test_collect() {
+        let mut tree = Tree::<char>::new();
+        let a = tree.add(None, 'a');
+        let b = tree.add(Some(a), 'b');
+        let c = tree.add(Some(a), 'c');
+        let _d = tree.add(Some(b), 'd');
+        let e = tree.add(Some(c), 'e');
+        let f = tree.add(Some(c), 'f');
+        let g = tree.add(Some(f), 'g');
+
+        // b and d lead to no leaf
+        let leaves = tree.collect(&[g, e]);
+        assert_eq!(5, tree.len());
+        assert_eq!("gfca", tree.iter(leaves[0]).collect::<String>());
+        assert_eq!("eca", tree.iter(leaves[1]).collect::<String>());
     }
 
     #[test]
-    fn test_box_tree() {
-        let mut node = Rc::new(Node::<Box<i32>> {
-            father: None,
-            value: Box::new(0),
-        });
-
+    fn test_long_tree() {
+        let mut tree = Tree::<i32>::new();
+        let mut node = tree.add(None, 0);
         for ii in 1..10 {
-            let child = Rc::new(Node::<Box<i32>> {
-                father: Some(Rc::clone(&node)),
-                value: Box::new(ii),
-            });
-
-            node = Rc::clone(&child);
+            node = tree.add(Some(node), ii);
         }
 
         let mut expected_value = 10;
-        for rr in TreeIterator::new(Rc::clone(&node)) {
+        for value in tree.iter(node) {
             expected_value -= 1;
-            assert_eq!(expected_value, *rr.value);
+            assert_eq!(expected_value, value);
         }
+        assert_eq!(0, expected_value);
     }
 }
@@ -0,0 +1,251 @@
+use crate::area::{Area, Position};
+use crate::generator::Generator;
+use crate::parser::Parser;
+use crate::path::{Constraint, Path};
+use crate::snake::brick::{Brick, Orientation, ORIENTATIONS};
+use crate::snake::chain::Chain;
+use crate::solution::Solution;
+use crate::symmetry::{representative_cells, Symmetry};
+use std::collections::HashSet;
+use std::vec::Vec;
+
+// all foldings of a chain into a box, counted up to symmetry
+pub struct Census {
+    pub solutions: Vec<Solution>,
+    // partial paths built by the solver, summed over all start bricks
+    pub nodes: usize,
+    // largest layer of partial paths
+    pub peak: usize,
+}
+
+impl Census {
+    pub fn is_unique(&self) -> bool {
+        self.solutions.len() == 1
+    }
+}
+
+/*
+ * counting mode: folds the chain from every start cell (up to symmetry)
+ * and every start orientation (and frame, for handed chains), stops as
+ * soon as more than `limit` distinct solutions are known (0: count all);
+ * constraints tie bricks to cells, the foldings are not counted up to
+ * symmetry or reversal then
+ */
+pub fn census(area: &Area, chain: &Chain, constraints: &[Constraint], limit: usize) -> Census {
+    let mut result = Census {
+        solutions: Vec::new(),
+        nodes: 0,
+        peak: 0,
+    };
+    let (lower, upper) = match area.bounds {
+        None => return result,
+        Some(bb) => bb,
+    };
+    let dims = (
+        upper.x - lower.x + 1,
+        upper.y - lower.y + 1,
+        upper.z - lower.z + 1,
+    );
+    let mut symmetries = Symmetry::of_area(area, chain.is_handed());
+    if !constraints.is_empty() {
+        symmetries.truncate(1);
+    }
+    let mut known = HashSet::new();
+
+    for start in representative_cells(dims, &symmetries) {
+        let start = Position::new(start.x + lower.x, start.y + lower.y, start.z + lower.z);
+        if !area.is_in(start) {
+            continue;
+        }
+        for (ori, up) in start_frames(chain) {
+            let mut path = Path::new(area.clone(), chain.copy());
+            path.constraints = constraints.to_vec();
+            path.add_brick(&Brick::new(start, ori, chain.get(0).unwrap()).with_up(up));
+            path.fold(false);
+            for (_, nn) in &path.statistics {
+                result.nodes += nn;
+                result.peak = result.peak.max(*nn);
+            }
+            for solution in path.solutions(false) {
+                let key = if constraints.is_empty() {
+                    solution.key(lower, &symmetries, chain.cyclic)
+                } else {
+                    solution
+                        .cells()
+                        .iter()
+                        .map(|pos| (pos.x, pos.y, pos.z))
+                        .collect()
+                };
+                if known.insert(key) {
+                    result.solutions.push(solution);
+                }
+            }
+            if limit > 0 && result.solutions.len() > limit {
+                return result;
+            }
+        }
+    }
+    result
+}
+
+// orientation and up of the first brick, up only matters for handed chains
+fn start_frames(chain: &Chain) -> Vec<(Orientation, Orientation)> {
+    let mut frames: Vec<(Orientation, Orientation)> = Vec::new();
+    for ori in ORIENTATIONS.iter() {
+        if !chain.is_handed() {
+            frames.push((*ori, ori.default_up()));
+            continue;
+        }
+        for up in ORIENTATIONS.iter() {
+            if up.is_perpendicular(*ori) {
+                frames.push((*ori, *up));
+            }
+        }
+    }
+    frames
+}
+
+/*
+ * proposes random chains and keeps those with exactly one solution,
+ * returns the puzzles together with their census
+ */
+pub fn design(generator: &mut Generator, tries: usize) -> Vec<(String, Census)> {
+    let mut result: Vec<(String, Census)> = Vec::new();
+    for _ in 0..tries {
+        let puzzle = match generator.puzzle(100) {
+            None => continue,
+            Some(puzzle) => puzzle,
+        };
+        let prsr = Parser::new(&puzzle);
+        let cns = census(&prsr.path.area, &prsr.path.chain, &prsr.path.constraints, 1);
+        if cns.is_unique() {
+            result.push((puzzle, cns));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Random;
+
+    #[test]
+    fn test_census() {
+        let prsr = Parser::new(
+            "area cube 3
+chain 27 SSTTTSTTSTTTSTSTTTTSTSTSTSS
+",
+        );
+        let cns = census(&prsr.path.area, &prsr.path.chain, &prsr.path.constraints, 0);
+        assert!(cns.is_unique());
+        assert!(cns.peak >= 32);
+        assert!(cns.nodes > cns.peak);
+
+        let prsr = Parser::new(
+            "area cube 2
+chain 8 STTTTTTS
+",
+        );
+        // the three shapes of hamiltonian paths through a 2x2x2 cube
+        let cns = census(&prsr.path.area, &prsr.path.chain, &prsr.path.constraints, 0);
+        assert_eq!(3, cns.solutions.len());
+        assert!(!cns.is_unique());
+        // fixed ends are counted as they are, no longer up to symmetry
+        let fixed = Parser::new(
+            "area cube 2
+chain 8 STTTTTTS
+fix 1 0 0 0
+end 0 0 1
+",
+        );
+        let cns = census(
+            &fixed.path.area,
+            &fixed.path.chain,
+            &fixed.path.constraints,
+            0,
+        );
+        assert_eq!(4, cns.solutions.len());
+        assert!(cns.solutions.iter().all(|solution| {
+            solution.bricks[0].coordinates == Position::new(0, 0, 0)
+                && solution.bricks[7].coordinates == Position::new(0, 0, 1)
+        }));
+        // counting stops early
+        assert!(
+            census(&prsr.path.area, &prsr.path.chain, &prsr.path.constraints, 1)
+                .solutions
+                .len()
+                > 1
+        );
+
+        // a straight line through a box can be folded in one way only
+        let prsr = Parser::new(
+            "area box 3 1 1
+chain 3 SSS
+",
+        );
+        assert!(census(&prsr.path.area, &prsr.path.chain, &prsr.path.constraints, 0).is_unique());
+
+        let prsr = Parser::new(
+            "area box 2 2 1
+chain 4 STTS
+",
+        );
+        assert!(census(&prsr.path.area, &prsr.path.chain, &prsr.path.constraints, 0).is_unique());
+
+        // handed bricks need every start frame
+        let prsr = Parser::new(
+            "area box 2 2 1
+chain 4 SLLS
+",
+        );
+        assert!(census(&prsr.path.area, &prsr.path.chain, &prsr.path.constraints, 0).is_unique());
+
+        // the two chiral shapes of the cube count twice for a handed chain
+        let prsr = Parser::new(
+            "area cube 2
+chain 8 STTTTTLS
+",
+        );
+        assert_eq!(
+            5,
+            census(&prsr.path.area, &prsr.path.chain, &prsr.path.constraints, 0)
+                .solutions
+                .len()
+        );
+    }
+
+    #[test]
+    fn test_design() {
+        let mut generator = Generator::new(Area::cuboid(3, 3, 2), Random::new(1));
+        for (puzzle, cns) in design(&mut generator, 5) {
+            assert!(puzzle.starts_with("area box 3 3 2\n"));
+            assert!(cns.is_unique());
+        }
+    }
+
+    #[test]
+    fn test_rings() {
+        // rotations of a ring are the same folding
+        let prsr = Parser::new(
+            "area bitmap ### #.# ###
+chain 8 TSTSTSTS cyclic
+",
+        );
+        assert!(census(&prsr.path.area, &prsr.path.chain, &prsr.path.constraints, 0).is_unique());
+
+        // one hamiltonian cycle of the cube up to symmetry, but several paths
+        let prsr = Parser::new(
+            "area cube 2
+chain 8 TTTTTTTT cyclic
+",
+        );
+        assert!(census(&prsr.path.area, &prsr.path.chain, &prsr.path.constraints, 0).is_unique());
+        let prsr = Parser::new(
+            "area cube 2
+chain 8 TTTTTTTT
+",
+        );
+        assert!(!census(&prsr.path.area, &prsr.path.chain, &prsr.path.constraints, 0).is_unique());
+    }
+}
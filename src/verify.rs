@@ -0,0 +1,515 @@
+use crate::area::{Area, Position};
+use crate::path::Constraint;
+use crate::snake::brick::{Brick, Orientation};
+use crate::snake::chain::{Chain, Form};
+use std::fmt;
+use std::vec::Vec;
+
+// everything that can be wrong with a proposed folding,
+// brick indices refer to the position in the chain
+#[derive(Debug, PartialEq)]
+pub enum Violation {
+    OutOfArea(usize, Position),
+    SelfIntersection(usize, Position),
+    Disconnected(usize),
+    OutOfRange(usize),
+    Straight(usize),
+    Turn(usize),
+    Joint(usize, Form),
+    Open(usize),
+    MissingOrientation(usize),
+    UnusedOrientations(usize),
+    Length(usize, usize),
+    Constraint(usize),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::OutOfArea(ii, pos) => write!(
+                f,
+                "brick {} leaves the area at [{}, {}, {}]",
+                ii + 1,
+                pos.x,
+                pos.y,
+                pos.z
+            ),
+            Violation::SelfIntersection(ii, pos) => write!(
+                f,
+                "brick {} intersects the chain at [{}, {}, {}]",
+                ii + 1,
+                pos.x,
+                pos.y,
+                pos.z
+            ),
+            Violation::Disconnected(ii) => {
+                write!(f, "brick {} is not adjacent to its predecessor", ii + 1)
+            }
+            Violation::OutOfRange(ii) => {
+                write!(f, "brick {} leaves the range of the coordinates", ii + 1)
+            }
+            Violation::Straight(ii) => write!(f, "straight brick {} changes orientation", ii + 1),
+            Violation::Turn(ii) => write!(f, "turn brick {} does not turn by 90 degrees", ii + 1),
+            Violation::Joint(ii, frm) => write!(
+                f,
+                "brick {} ({}) turns in a way its joint does not allow",
+                ii + 1,
+                frm.to_char()
+            ),
+            Violation::Open(ii) => {
+                write!(f, "ring is not closed between brick {} and brick 1", ii + 1)
+            }
+            Violation::MissingOrientation(ii) => {
+                write!(f, "no orientation left for turn brick {}", ii + 1)
+            }
+            Violation::UnusedOrientations(nn) => {
+                write!(f, "{} orientations left after the last brick", nn)
+            }
+            Violation::Length(nbricks, nchain) => write!(
+                f,
+                "folding has {} bricks but the chain has {}",
+                nbricks, nchain
+            ),
+            Violation::Constraint(ii) => {
+                write!(f, "brick {} is not where a fix or end line puts it", ii + 1)
+            }
+        }
+    }
+}
+
+/*
+ * build the bricks of a folding from its orientation switches,
+ * i.e. the first orientation and one new orientation per turn
+ * (per brick which is not straight, for the other joints);
+ * up is the up of the first frame, if the chain has handed bricks
+ */
+pub fn replay(
+    chain: &Chain,
+    start: Position,
+    up: Option<Orientation>,
+    orientations: &[Orientation],
+) -> Result<Vec<Brick>, Violation> {
+    let mut bricks: Vec<Brick> = Vec::new();
+    let mut ot = orientations.iter();
+
+    for (ii, frm) in chain.dirs.iter().enumerate() {
+        let brk = match bricks.last() {
+            None => match ot.next() {
+                None => return Err(Violation::MissingOrientation(ii)),
+                Some(ori) => {
+                    let brk = Brick::new(start, *ori, *frm);
+                    match up {
+                        Some(up) => brk.with_up(up),
+                        None => brk,
+                    }
+                }
+            },
+            Some(last) => match frm {
+                Form::Straight => last.next_straight(),
+                _ => match ot.next() {
+                    None => return Err(Violation::MissingOrientation(ii)),
+                    Some(ori) => last.next_orientation(ori, *frm),
+                },
+            }
+            .ok_or(Violation::OutOfRange(ii))?,
+        };
+        bricks.push(brk);
+    }
+
+    match ot.count() {
+        0 => Ok(bricks),
+        nn => Err(Violation::UnusedOrientations(nn)),
+    }
+}
+
+/*
+ * check a complete folding brick by brick, the first violation
+ * along the chain is reported
+ */
+pub fn check(
+    area: &Area,
+    chain: &Chain,
+    constraints: &[Constraint],
+    bricks: &[Brick],
+) -> Result<(), Violation> {
+    if bricks.len() != chain.len() {
+        return Err(Violation::Length(bricks.len(), chain.len()));
+    }
+
+    // frame carried along the chain, starting with the one of the first brick
+    let mut frame: Option<Brick> = None;
+    for (ii, brk) in bricks.iter().enumerate() {
+        if let Some(last) = frame {
+            if area.lattice.step(last.coordinates, last.orientation) != Some(brk.coordinates) {
+                return Err(Violation::Disconnected(ii));
+            }
+            let frm = chain.get(ii).unwrap();
+            if !area.lattice.allows(&last, frm, brk.orientation) {
+                return Err(match frm {
+                    Form::Straight => Violation::Straight(ii),
+                    Form::Turn => Violation::Turn(ii),
+                    _ => Violation::Joint(ii, frm),
+                });
+            }
+        }
+        frame = Some(match frame {
+            None => *brk,
+            // adjacent to the last brick, so within the range
+            Some(last) => last
+                .next_orientation(&brk.orientation, chain.get(ii).unwrap())
+                .ok_or(Violation::Disconnected(ii))?,
+        });
+        if !area.is_in(brk.coordinates) {
+            return Err(Violation::OutOfArea(ii, brk.coordinates));
+        }
+        for other in &bricks[..ii] {
+            if other.coordinates == brk.coordinates {
+                return Err(Violation::SelfIntersection(ii, brk.coordinates));
+            }
+        }
+        if constraints.iter().any(|cons| {
+            cons.index == ii
+                && (cons.position != brk.coordinates
+                    || cons.orientation.is_some_and(|ori| ori != brk.orientation))
+        }) {
+            return Err(Violation::Constraint(ii));
+        }
+    }
+    // closing joint of a ring
+    if let (true, Some(last), Some(first)) = (chain.cyclic, frame, bricks.first()) {
+        if area.lattice.step(last.coordinates, last.orientation) != Some(first.coordinates)
+            || !area
+                .lattice
+                .allows(&last, chain.get(0).unwrap(), first.orientation)
+        {
+            return Err(Violation::Open(bricks.len() - 1));
+        }
+    }
+    Ok(())
+}
+
+pub fn verify(
+    area: &Area,
+    chain: &Chain,
+    constraints: &[Constraint],
+    start: Position,
+    up: Option<Orientation>,
+    orientations: &[Orientation],
+) -> Result<(), Violation> {
+    let bricks = replay(chain, start, up, orientations)?;
+    check(area, chain, constraints, &bricks)
+}
+
+pub fn parse_orientations(input: &str) -> Vec<Orientation> {
+    input.chars().filter_map(Orientation::from_char).collect()
+}
+
+/*
+ * inverse of replay: first orientation and the orientation of every brick
+ * which is not straight, for chains of straight bricks and turns these
+ * are the changes of orientation
+ */
+pub fn orientation_switches(bricks: &[Brick]) -> String {
+    let mut output = String::new();
+    for (ii, brk) in bricks.iter().enumerate() {
+        match brk.form {
+            Form::Straight if ii > 0 => {}
+            _ => output.push(brk.orientation.to_char()),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::area::Coord;
+    use crate::parser::Parser;
+
+    const PUZZLE: &str = "area cube 3
+chain 27 SSTTTSTTSTTTSTSTTTTSTSTSTSS
+path NE
+start 0 0 0
+";
+
+    fn check_folding(switches: &str) -> Result<(), Violation> {
+        let parser = Parser::new(PUZZLE);
+        verify(
+            &parser.path.area,
+            &parser.path.chain,
+            &parser.path.constraints,
+            parser.start,
+            parser.up,
+            &parse_orientations(switches),
+        )
+    }
+
+    #[test]
+    fn test_valid_folding() {
+        assert_eq!(Ok(()), check_folding("NESUEDNUWSEDEUWNE"));
+    }
+
+    #[test]
+    fn test_invalid_foldings() {
+        assert_eq!(
+            Err(Violation::OutOfArea(3, Position::new(2, -1, 0))),
+            check_folding("NWSUEDNUWSEDEUWNE")
+        );
+        assert_eq!(
+            Err(Violation::SelfIntersection(5, Position::new(1, 0, 0))),
+            check_folding("NESWEDNUWSEDEUWNE")
+        );
+        assert_eq!(Err(Violation::Turn(3)), check_folding("NEEUEDNUWSEDEUWNE"));
+        assert_eq!(
+            Err(Violation::MissingOrientation(24)),
+            check_folding("NESUEDNUWSEDEUWN")
+        );
+        assert_eq!(
+            Err(Violation::UnusedOrientations(1)),
+            check_folding("NESUEDNUWSEDEUWNES")
+        );
+
+        // no position beyond the end of the coordinate range
+        let chain = Parser::new(PUZZLE).path.chain;
+        assert_eq!(
+            Some(Violation::OutOfRange(1)),
+            replay(
+                &chain,
+                Position::new(Coord::MAX, 0, 0),
+                None,
+                &parse_orientations("NESUEDNUWSEDEUWNE")
+            )
+            .err()
+        );
+    }
+
+    #[test]
+    fn test_check_bricks() {
+        let parser = Parser::new(PUZZLE);
+        let mut bricks = replay(
+            &parser.path.chain,
+            parser.start,
+            parser.up,
+            &parse_orientations("NESUEDNUWSEDEUWNE"),
+        )
+        .unwrap();
+        bricks[1].orientation = Orientation::East;
+        assert_eq!(
+            Err(Violation::Straight(1)),
+            check(
+                &parser.path.area,
+                &parser.path.chain,
+                &parser.path.constraints,
+                &bricks
+            )
+        );
+        bricks.pop();
+        assert_eq!(
+            Err(Violation::Length(26, 27)),
+            check(
+                &parser.path.area,
+                &parser.path.chain,
+                &parser.path.constraints,
+                &bricks
+            )
+        );
+
+        // the fix and end lines of the puzzle hold as well
+        let folding = |input: &str| {
+            let parser = Parser::new(input);
+            let bricks = replay(
+                &parser.path.chain,
+                parser.start,
+                parser.up,
+                &parse_orientations("NESUEDNUWSEDEUWNE"),
+            )
+            .unwrap();
+            check(
+                &parser.path.area,
+                &parser.path.chain,
+                &parser.path.constraints,
+                &bricks,
+            )
+        };
+        assert_eq!(Ok(()), folding(&format!("{}fix 3 2 0 0 E\n", PUZZLE)));
+        assert_eq!(
+            Err(Violation::Constraint(2)),
+            folding(&format!("{}fix 3 2 0 0 N\n", PUZZLE))
+        );
+        assert_eq!(
+            Err(Violation::Constraint(2)),
+            folding(&format!("{}fix 3 0 0 1\n", PUZZLE))
+        );
+    }
+
+    #[test]
+    fn test_joints() {
+        let mut parser = Parser::new(
+            "area box 2 2 1
+chain 4 STPS
+path NE
+start 0 0 0
+",
+        );
+        let (area, chain) = (&parser.path.area, &parser.path.chain);
+        // the third brick has to stay in the plane of north and east
+        assert_eq!(
+            Ok(()),
+            verify(
+                area,
+                chain,
+                &parser.path.constraints,
+                parser.start,
+                parser.up,
+                &parse_orientations("NES")
+            )
+        );
+        assert_eq!(
+            Err(Violation::Joint(2, Form::Planar)),
+            verify(
+                area,
+                chain,
+                &parser.path.constraints,
+                parser.start,
+                parser.up,
+                &parse_orientations("NEU")
+            )
+        );
+        parser.path.fold(false);
+        let solutions: Vec<String> = parser
+            .path
+            .solutions(false)
+            .map(|solution| solution.orientations())
+            .collect();
+        assert_eq!(vec!["NES"], solutions);
+
+        let parser = Parser::new(
+            "area box 2 2 1
+chain 4 SXXX
+path N
+start 0 0 0
+",
+        );
+        let (area, chain) = (&parser.path.area, &parser.path.chain);
+        // free joints need an orientation whether they turn or not
+        assert_eq!(
+            Ok(()),
+            verify(
+                area,
+                chain,
+                &parser.path.constraints,
+                parser.start,
+                parser.up,
+                &parse_orientations("NESS")
+            )
+        );
+        assert_eq!(
+            Err(Violation::MissingOrientation(3)),
+            verify(
+                area,
+                chain,
+                &parser.path.constraints,
+                parser.start,
+                parser.up,
+                &parse_orientations("NES")
+            )
+        );
+    }
+
+    #[test]
+    fn test_handed() {
+        let fold = |input: &str| {
+            let mut parser = Parser::new(input);
+            parser.path.fold(false);
+            parser
+                .path
+                .solutions(false)
+                .map(|solution| solution.orientations())
+                .collect::<Vec<String>>()
+        };
+        let solutions = fold(
+            "area box 2 2 1
+chain 4 SRRS
+path N
+start 0 0 0
+",
+        );
+        assert_eq!(vec!["NES"], solutions);
+        // left turns lead out of the box
+        let solutions = fold(
+            "area box 2 2 1
+chain 4 SLLS
+path N
+start 0 0 0
+",
+        );
+        assert!(solutions.is_empty());
+        // unless the frame is upside down
+        let solutions = fold(
+            "area box 2 2 1
+chain 4 SLLS
+path N
+up D
+start 0 0 0
+",
+        );
+        assert_eq!(1, solutions.len());
+
+        let parser = Parser::new(
+            "area box 2 2 1
+chain 4 SLLS
+up D
+start 0 0 0
+",
+        );
+        let (area, chain) = (&parser.path.area, &parser.path.chain);
+        assert_eq!(
+            Ok(()),
+            verify(
+                area,
+                chain,
+                &parser.path.constraints,
+                parser.start,
+                parser.up,
+                &parse_orientations("NES")
+            )
+        );
+        assert_eq!(
+            Err(Violation::Joint(1, Form::Left)),
+            verify(
+                area,
+                chain,
+                &parser.path.constraints,
+                parser.start,
+                None,
+                &parse_orientations("NES")
+            )
+        );
+    }
+
+    #[test]
+    fn test_cyclic() {
+        let parser = Parser::new(
+            "area box 2 2 1
+chain 4 TTTT cyclic
+start 0 0 0
+",
+        );
+        let check_ring = |switches: &str| {
+            verify(
+                &parser.path.area,
+                &parser.path.chain,
+                &parser.path.constraints,
+                parser.start,
+                None,
+                &parse_orientations(switches),
+            )
+        };
+        assert_eq!(Ok(()), check_ring("NESW"));
+        assert_eq!(Err(Violation::Open(3)), check_ring("NESE"));
+        assert_eq!(
+            "ring is not closed between brick 4 and brick 1",
+            format!("{}", Violation::Open(3))
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use crate::area::Area;
+use crate::render::grid;
+use crate::solution::Solution;
+use std::io::{BufRead, Result, Write};
+
+const CLEAR: &str = "\x1b[2J\x1b[H";
+const CURRENT: &str = "1;7";
+const SEGMENT: &str = "1;33";
+
+// steps through a folding brick by brick, from start to end
+pub struct Viewer<'a> {
+    area: &'a Area,
+    solution: Solution,
+    pub step: usize,
+}
+
+impl<'a> Viewer<'a> {
+    pub fn new(area: &'a Area, solution: Solution) -> Viewer<'a> {
+        Viewer {
+            area,
+            solution,
+            step: 1,
+        }
+    }
+
+    pub fn forward(&mut self) {
+        if self.step < self.solution.bricks.len() {
+            self.step += 1;
+        }
+    }
+
+    pub fn backward(&mut self) {
+        if self.step > 1 {
+            self.step -= 1;
+        }
+    }
+
+    pub fn jump(&mut self, step: usize) {
+        self.step = step.max(1).min(self.solution.bricks.len());
+    }
+
+    pub fn frame(&self) -> String {
+        let current = self.step - 1;
+        let segs = self.solution.segments();
+        let (segment, seg) = segs
+            .iter()
+            .enumerate()
+            .find(|(_, seg)| seg.0 <= current && current <= seg.1)
+            .unwrap();
+
+        let mut output = format!(
+            "brick {}/{}, segment {}/{} (bricks {}-{})\n",
+            self.step,
+            self.solution.bricks.len(),
+            segment + 1,
+            segs.len(),
+            seg.0 + 1,
+            seg.1 + 1
+        );
+        output.push_str(&match self.solution.bricks.get(self.step) {
+            None => "end of chain\n".to_string(),
+            Some(next) if next.orientation == self.solution.bricks[current].orientation => format!(
+                "next joint: straight, keep {}\n",
+                next.orientation.to_char()
+            ),
+            Some(next) => format!(
+                "next joint: turn from {} to {}\n",
+                self.solution.bricks[current].orientation.to_char(),
+                next.orientation.to_char()
+            ),
+        });
+        output.push('\n');
+        output.push_str(&grid(self.area, &self.solution.bricks, self.step, |ii| {
+            if ii == current {
+                Some(CURRENT)
+            } else if seg.0 <= ii && ii <= seg.1 {
+                Some(SEGMENT)
+            } else {
+                None
+            }
+        }));
+        output
+    }
+
+    // line based, works on any ansi terminal without raw mode
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, output: &mut W) -> Result<()> {
+        let mut lines = input.lines();
+        loop {
+            write!(output, "{}{}", CLEAR, self.frame())?;
+            write!(
+                output,
+                "[enter/n] next  [p] previous  [<number>] jump  [q] quit > "
+            )?;
+            output.flush()?;
+
+            let line = match lines.next() {
+                None => break,
+                Some(line) => line?,
+            };
+            match line.trim() {
+                "" | "n" => self.forward(),
+                "p" => self.backward(),
+                "q" => break,
+                cmd => {
+                    if let Ok(step) = cmd.parse() {
+                        self.jump(step);
+                    }
+                }
+            }
+        }
+        writeln!(output)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::cube_folding;
+
+    #[test]
+    fn test_viewer() {
+        let (parser, bricks) = cube_folding();
+        let mut viewer = Viewer::new(&parser.path.area, Solution::new(bricks));
+
+        assert!(viewer
+            .frame()
+            .starts_with("brick 1/27, segment 1/17 (bricks 1-2)\nnext joint: straight, keep N\n"));
+        viewer.backward();
+        assert_eq!(1, viewer.step);
+        viewer.forward();
+        assert!(viewer.frame().contains("next joint: turn from N to E\n"));
+        // the active segment is highlighted, the current brick inverted
+        assert!(viewer.frame().contains("\x1b[1;33m  1N\x1b[0m"));
+        assert!(viewer.frame().contains("\x1b[1;7m  2N\x1b[0m"));
+        assert!(!viewer.frame().contains("3E"));
+
+        viewer.jump(100);
+        assert_eq!(27, viewer.step);
+        assert!(viewer.frame().contains("end of chain"));
+
+        let mut output: Vec<u8> = Vec::new();
+        viewer.run("p\n\n5\nq\n".as_bytes(), &mut output).unwrap();
+        assert_eq!(5, viewer.step);
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(4, output.matches(CLEAR).count());
+        assert!(output.contains("brick 26/27"));
+    }
+}